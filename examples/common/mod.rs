@@ -0,0 +1,148 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Genome shared by the network examples: the weights of a network with a fixed topology.
+
+use std::convert::TryInto;
+
+use rand::Rng;
+
+use rustneat::activations::Activation;
+use rustneat::genome::Genome;
+use rustneat::speciation::Individual;
+
+/// Probability of every weight to be perturbed by a mutation
+const MUTATION_RATE: f64 = 0.2;
+/// Maximum perturbation of a weight by a mutation of strength 1
+const MUTATION_POWER: f64 = 0.5;
+/// Maximum mean difference of the weights of two compatible genomes
+const COMPATIBILITY_THRESHOLD: f64 = 0.5;
+
+/// Feed-forward network with one hidden layer, every neuron has a bias
+pub struct Topology {
+    pub inputs: usize,
+    pub hidden: usize,
+    pub outputs: usize,
+    pub activation: Activation,
+}
+
+impl Topology {
+    /// Number of weights (biases included) of the network
+    pub const fn weight_count(&self) -> usize {
+        self.hidden * (self.inputs + 1) + self.outputs * (self.hidden + 1)
+    }
+
+    /// Outputs of the network with the given weights for the inputs
+    pub fn activate(&self, weights: &[f64], inputs: &[f64]) -> Vec<f64> {
+        assert_eq!(weights.len(), self.weight_count());
+        assert_eq!(inputs.len(), self.inputs);
+        let (hidden_weights, output_weights) = weights.split_at(self.hidden * (self.inputs + 1));
+        let hidden: Vec<f64> = hidden_weights.chunks(self.inputs + 1)
+            .map(|neuron| self.activation.activate(weighted_sum(neuron, inputs)))
+            .collect();
+        output_weights.chunks(self.hidden + 1)
+            .map(|neuron| self.activation.activate(weighted_sum(neuron, &hidden)))
+            .collect()
+    }
+}
+
+/// Bias (the last weight) plus the weighted inputs
+fn weighted_sum(weights: &[f64], inputs: &[f64]) -> f64 {
+    let (bias, weights) = weights.split_last().unwrap();
+    bias + weights.iter().zip(inputs).map(|(w, x)| w * x).sum::<f64>()
+}
+
+/// The `N` weights of a network
+#[derive(Clone, Debug)]
+pub struct Weights<const N: usize> {
+    pub weights: Vec<f64>,
+    pub fitness: Option<f64>,
+}
+
+impl<const N: usize> Weights<N> {
+    /// Stores the fitness and returns it, as expected from the evaluation functions
+    pub fn set_fitness(&mut self, fitness: f64) -> f64 {
+        self.fitness = Some(fitness);
+        fitness
+    }
+}
+
+impl<const N: usize> Individual<f64> for Weights<N> {
+    fn fitness(&self) -> Option<f64> {
+        self.fitness
+    }
+
+    fn is_compatible(&self, other: &Self) -> bool {
+        self.distance(other).unwrap() <= COMPATIBILITY_THRESHOLD
+    }
+
+    fn distance(&self, other: &Self) -> Option<f64> {
+        Some(self.weights.iter().zip(other.weights.iter()).map(|(a, b)| (a - b).abs()).sum::<f64>() / N as f64)
+    }
+}
+
+impl<const N: usize> Genome<f64> for Weights<N> {
+    fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            weights: (0..N).map(|_| rng.gen_range(-1.0..1.0)).collect(),
+            fitness: None,
+        }
+    }
+
+    fn reproduce<R: Rng + ?Sized>(&self, _rng: &mut R) -> Self {
+        Self {
+            weights: self.weights.clone(),
+            fitness: None,
+        }
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        Self {
+            weights: self.weights.iter().zip(other.weights.iter())
+                .map(|(a, b)| if rng.gen() { *a } else { *b })
+                .collect(),
+            fitness: None,
+        }
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.mutate_with_strength(1.0, rng)
+    }
+
+    fn mutate_with_strength<R: Rng + ?Sized>(&mut self, strength: f64, rng: &mut R) {
+        let power = MUTATION_POWER * strength;
+        for weight in self.weights.iter_mut() {
+            if rng.gen_bool(MUTATION_RATE) {
+                *weight += rng.gen_range(-power..=power);
+            }
+        }
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        Some(self.weights.iter().flat_map(|weight| weight.to_le_bytes()).collect())
+    }
+
+    fn deserialize(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != N * 8 {
+            return None;
+        }
+        Some(Self {
+            weights: bytes.chunks(8).map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap())).collect(),
+            fitness: None,
+        })
+    }
+}
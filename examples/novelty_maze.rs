@@ -0,0 +1,133 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Novelty maze: a robot driven by a network has to reach the goal of a deceptive maze, where the
+//! distance to the goal leads into a dead end. The fitness rewards the novelty of where a robot ends up
+//! (novelty search) instead of the distance to the goal.
+
+mod common;
+
+use std::cell::{Cell, RefCell};
+use std::io;
+
+use rustneat::activations::Activation;
+use rustneat::harness::{ExampleRunner, Preset, Report};
+
+use common::{Topology, Weights};
+
+/// `#` are walls, the robot starts in `S` and has to reach `G`.
+/// Going straight towards the goal ends in the pocket under it.
+const MAZE: [&str; 10] = [
+    "##########",
+    "#G       #",
+    "#######  #",
+    "#     #  #",
+    "#  #  #  #",
+    "#  #     #",
+    "#  ##### #",
+    "#        #",
+    "#S       #",
+    "##########",
+];
+const STEPS: usize = 40;
+/// Moves of the robot, one for each output of the network
+const MOVES: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Inputs: position and time, outputs: preference of every move
+const TOPOLOGY: Topology = Topology { inputs: 3, hidden: 6, outputs: 4, activation: Activation::Tanh };
+type Robot = Weights<{ TOPOLOGY.weight_count() }>;
+
+/// Number of nearest behaviors of the archive measuring the novelty
+const NEAREST: usize = 5;
+
+type Position = (i32, i32);
+
+fn find(cell: u8) -> Position {
+    MAZE.iter().enumerate()
+        .find_map(|(y, row)| row.bytes().position(|c| c == cell).map(|x| (x as i32, y as i32)))
+        .unwrap()
+}
+
+fn is_wall((x, y): Position) -> bool {
+    MAZE[y as usize].as_bytes()[x as usize] == b'#'
+}
+
+/// Where the robot ends up, the behavior of the robot
+fn simulate(robot: &Robot) -> Position {
+    let (width, height) = (MAZE[0].len() as f64, MAZE.len() as f64);
+    let mut position = find(b'S');
+    for step in 0..STEPS {
+        let inputs = [position.0 as f64 / width, position.1 as f64 / height, step as f64 / STEPS as f64];
+        let outputs = TOPOLOGY.activate(&robot.weights, &inputs);
+        let choice = (0..MOVES.len()).max_by(|&a, &b| outputs[a].total_cmp(&outputs[b])).unwrap();
+        let next = (position.0 + MOVES[choice].0, position.1 + MOVES[choice].1);
+        if !is_wall(next) {
+            position = next;
+        }
+    }
+    position
+}
+
+/// Mean distance of the behavior to its nearest behaviors in the archive
+fn novelty(behavior: Position, archive: &[Position]) -> f64 {
+    let mut distances: Vec<f64> = archive.iter()
+        .map(|other| (((behavior.0 - other.0).pow(2) + (behavior.1 - other.1).pow(2)) as f64).sqrt())
+        .collect();
+    distances.sort_by(f64::total_cmp);
+    distances.truncate(NEAREST);
+    if distances.is_empty() {
+        return 1.0;
+    }
+    distances.iter().sum::<f64>() / distances.len() as f64
+}
+
+fn main() -> io::Result<()> {
+    let runner = ExampleRunner::with_preset(Preset::Diverse, 100, 42, 100, None);
+
+    let goal = find(b'G');
+    // every behavior found so far, once
+    let archive: RefCell<Vec<Position>> = RefCell::new(Vec::new());
+    let solved_in: Cell<Option<usize>> = Cell::new(None);
+    let generation = Cell::new(0);
+
+    let outcome = runner.run(
+        |robot: &mut Robot| {
+            let behavior = simulate(robot);
+            if behavior == goal && solved_in.get().is_none() {
+                solved_in.set(Some(generation.get()));
+            }
+            let novelty = novelty(behavior, &archive.borrow());
+            if !archive.borrow().contains(&behavior) {
+                archive.borrow_mut().push(behavior);
+            }
+            robot.set_fitness(novelty)
+        },
+        |report| match report {
+            Report::Start { manifest } => println!("{}", manifest),
+            Report::Generation(genus) => {
+                generation.set(genus.generation());
+                println!("generation {}: {} species, {} behaviors found", genus.generation(), genus.species_count(), archive.borrow().len());
+            }
+        },
+    )?;
+
+    match solved_in.get() {
+        Some(generation) => println!("Goal reached in generation {}", generation),
+        None => println!("Goal not reached in {} generations", outcome.genus.generation()),
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! OneMax: evolve a bit string with all the bits set.
+
+use std::io;
+
+use rand::Rng;
+
+use rustneat::genome::Genome;
+use rustneat::harness::{ExampleRunner, Preset, Report, Termination};
+use rustneat::speciation::Individual;
+
+const GENOME_SIZE: usize = 32;
+
+#[derive(Clone, Debug)]
+struct BitString {
+    bits: Vec<bool>,
+    fitness: Option<f64>,
+}
+
+impl BitString {
+    fn evaluate(&mut self) -> f64 {
+        let fitness = self.bits.iter().filter(|b| **b).count() as f64;
+        self.fitness = Some(fitness);
+        fitness
+    }
+}
+
+impl Individual<f64> for BitString {
+    fn fitness(&self) -> Option<f64> {
+        self.fitness
+    }
+
+    fn is_compatible(&self, other: &Self) -> bool {
+        self.distance(other).unwrap() <= (GENOME_SIZE / 4) as f64
+    }
+
+    fn distance(&self, other: &Self) -> Option<f64> {
+        Some(self.bits.iter().zip(other.bits.iter()).filter(|(a, b)| a != b).count() as f64)
+    }
+}
+
+impl Genome<f64> for BitString {
+    fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            bits: (0..GENOME_SIZE).map(|_| rng.gen()).collect(),
+            fitness: None,
+        }
+    }
+
+    fn reproduce<R: Rng + ?Sized>(&self, _rng: &mut R) -> Self {
+        Self {
+            bits: self.bits.clone(),
+            fitness: None,
+        }
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        Self {
+            bits: self.bits.iter().zip(other.bits.iter())
+                .map(|(a, b)| if rng.gen() { *a } else { *b })
+                .collect(),
+            fitness: None,
+        }
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let pos = rng.gen_range(0..self.bits.len());
+        self.bits[pos] = !self.bits[pos];
+    }
+}
+
+fn main() -> io::Result<()> {
+    let runner = ExampleRunner::with_preset(Preset::Neat, 50, 42, 500, Some(GENOME_SIZE as f64));
+
+    let outcome = runner.run(
+        |individual: &mut BitString| individual.evaluate(),
+        |report| match report {
+            Report::Start { manifest } => println!("{}", manifest),
+            Report::Generation(genus) => println!("generation {}: {} species", genus.generation(), genus.species_count()),
+        },
+    )?;
+
+    match outcome.termination {
+        Termination::TargetFitness => println!("Solved in {} generations", outcome.genus.generation()),
+        Termination::MaxGenerations => println!("Not solved, best fitness {}", outcome.best_fitness),
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Pole balancing: evolve a network pushing a cart to keep a pole upright (the classic single pole task).
+
+mod common;
+
+use std::io;
+
+use rustneat::activations::Activation;
+use rustneat::harness::{ExampleRunner, Preset, Report, Termination};
+
+use common::{Topology, Weights};
+
+const TOPOLOGY: Topology = Topology { inputs: 4, hidden: 4, outputs: 1, activation: Activation::Tanh };
+type Controller = Weights<{ TOPOLOGY.weight_count() }>;
+
+/// The fitness is the number of steps the pole stays up
+const MAX_STEPS: usize = 1000;
+
+const GRAVITY: f64 = 9.8;
+const CART_MASS: f64 = 1.0;
+const POLE_MASS: f64 = 0.1;
+/// Half the length of the pole
+const POLE_LENGTH: f64 = 0.5;
+const MAX_FORCE: f64 = 10.0;
+const TIME_STEP: f64 = 0.02;
+const TRACK_LIMIT: f64 = 2.4;
+const ANGLE_LIMIT: f64 = 12.0 * std::f64::consts::PI / 180.0;
+
+/// Position and velocity of the cart, angle and angular velocity of the pole
+#[derive(Clone, Copy, Default)]
+struct CartPole {
+    x: f64,
+    x_dot: f64,
+    theta: f64,
+    theta_dot: f64,
+}
+
+impl CartPole {
+    /// Advances the simulation by one time step (Euler integration) pushing the cart with `force`
+    fn step(&mut self, force: f64) {
+        let total_mass = CART_MASS + POLE_MASS;
+        let (sin, cos) = self.theta.sin_cos();
+        let temp = (force + POLE_MASS * POLE_LENGTH * self.theta_dot.powi(2) * sin) / total_mass;
+        let theta_acc = (GRAVITY * sin - cos * temp)
+            / (POLE_LENGTH * (4.0 / 3.0 - POLE_MASS * cos.powi(2) / total_mass));
+        let x_acc = temp - POLE_MASS * POLE_LENGTH * theta_acc * cos / total_mass;
+
+        self.x += TIME_STEP * self.x_dot;
+        self.x_dot += TIME_STEP * x_acc;
+        self.theta += TIME_STEP * self.theta_dot;
+        self.theta_dot += TIME_STEP * theta_acc;
+    }
+
+    fn failed(&self) -> bool {
+        self.x.abs() > TRACK_LIMIT || self.theta.abs() > ANGLE_LIMIT
+    }
+
+    /// State scaled to roughly [-1, 1], the inputs of the controller
+    fn inputs(&self) -> [f64; 4] {
+        [self.x / TRACK_LIMIT, self.x_dot / 2.0, self.theta / ANGLE_LIMIT, self.theta_dot / 2.0]
+    }
+}
+
+fn evaluate(controller: &mut Controller) -> f64 {
+    let mut cart_pole = CartPole { theta: 0.05, ..CartPole::default() };
+    let mut steps = 0;
+    while steps < MAX_STEPS && !cart_pole.failed() {
+        let output = TOPOLOGY.activate(&controller.weights, &cart_pole.inputs())[0];
+        cart_pole.step(MAX_FORCE * output);
+        steps += 1;
+    }
+    controller.set_fitness(steps as f64)
+}
+
+fn main() -> io::Result<()> {
+    let runner = ExampleRunner::with_preset(Preset::Diverse, 100, 42, 200, Some(MAX_STEPS as f64));
+
+    let outcome = runner.run(
+        evaluate,
+        |report| match report {
+            Report::Start { manifest } => println!("{}", manifest),
+            Report::Generation(genus) => println!("generation {}: {} species", genus.generation(), genus.species_count()),
+        },
+    )?;
+
+    match outcome.termination {
+        Termination::TargetFitness => println!("Balanced for {} steps after {} generations", MAX_STEPS, outcome.genus.generation()),
+        Termination::MaxGenerations => println!("Not solved, best fitness {}", outcome.best_fitness),
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! XOR: evolve the weights of a small network computing the exclusive or of its two inputs.
+//! With `--checkpoint <file>` the population is saved every 10 generations, `--resume <file>`
+//! continues a run from the saved population.
+
+mod common;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+
+use rustneat::activations::Activation;
+use rustneat::harness::{Checkpointing, ExampleRunner, Preset, Report, Termination};
+use rustneat::population::checkpoint::Checkpoint;
+
+use common::{Topology, Weights};
+
+const TOPOLOGY: Topology = Topology { inputs: 2, hidden: 3, outputs: 1, activation: Activation::Sigmoid };
+type Network = Weights<{ TOPOLOGY.weight_count() }>;
+
+const CASES: [([f64; 2], f64); 4] = [
+    ([0.0, 0.0], 0.0),
+    ([0.0, 1.0], 1.0),
+    ([1.0, 0.0], 1.0),
+    ([1.0, 1.0], 0.0),
+];
+/// The fitness is 4 minus the squared error over the cases
+const TARGET_FITNESS: f64 = 3.9;
+
+fn evaluate(network: &mut Network) -> f64 {
+    let error: f64 = CASES.iter()
+        .map(|(inputs, expected)| (TOPOLOGY.activate(&network.weights, inputs)[0] - expected).powi(2))
+        .sum();
+    network.set_fitness(4.0 - error)
+}
+
+fn main() -> io::Result<()> {
+    let mut runner = ExampleRunner::with_preset(Preset::Neat, 150, 42, 300, Some(TARGET_FITNESS));
+    let mut args = env::args().skip(1);
+    let mut resume: Option<Checkpoint> = None;
+    while let Some(arg) = args.next() {
+        let path = PathBuf::from(args.next().expect("missing the file of the checkpoint"));
+        match arg.as_str() {
+            "--checkpoint" => runner.checkpointing = Some(Checkpointing { path, interval: 10 }),
+            "--resume" => resume = Some(Checkpoint::read(BufReader::new(File::open(path)?))?),
+            _ => panic!("unknown argument {}", arg),
+        }
+    }
+
+    let report = |report: Report<Network, f64>| match report {
+        Report::Start { manifest } => println!("{}", manifest),
+        Report::Generation(genus) => println!("generation {}: {} species", genus.generation(), genus.species_count()),
+    };
+    let outcome = match resume {
+        Some(checkpoint) => runner.resume(&checkpoint, evaluate, report)?,
+        None => runner.run(evaluate, report)?,
+    };
+
+    match outcome.termination {
+        Termination::TargetFitness => println!("Solved in {} generations", outcome.genus.generation()),
+        Termination::MaxGenerations => println!("Not solved, best fitness {}", outcome.best_fitness),
+    }
+    Ok(())
+}
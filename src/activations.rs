@@ -0,0 +1,110 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Activation functions available to the nodes of a network.
+/// The same enum is meant to be stored in the genes and used when evaluating the network.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Activation {
+    Identity,
+    #[default]
+    Sigmoid,
+    Tanh,
+    Relu,
+    Sin,
+    Gauss,
+    Abs,
+    Step,
+    Softplus,
+    Clamped,
+    Square,
+    Cube,
+}
+
+impl Activation {
+    /// All the available activation functions
+    pub const ALL: [Activation; 12] = [
+        Activation::Identity,
+        Activation::Sigmoid,
+        Activation::Tanh,
+        Activation::Relu,
+        Activation::Sin,
+        Activation::Gauss,
+        Activation::Abs,
+        Activation::Step,
+        Activation::Softplus,
+        Activation::Clamped,
+        Activation::Square,
+        Activation::Cube,
+    ];
+
+    /// Applies the activation function to `x`
+    pub fn activate<F: num::Float>(&self, x: F) -> F {
+        match self {
+            Activation::Identity => x,
+            Activation::Sigmoid => F::one() / (F::one() + (-x).exp()),
+            Activation::Tanh => x.tanh(),
+            Activation::Relu => x.max(F::zero()),
+            Activation::Sin => x.sin(),
+            Activation::Gauss => (-(x * x)).exp(),
+            Activation::Abs => x.abs(),
+            Activation::Step => if x > F::zero() { F::one() } else { F::zero() },
+            // ln(1 + e^x), written to not overflow for large x
+            Activation::Softplus => x.max(F::zero()) + (-x.abs()).exp().ln_1p(),
+            Activation::Clamped => x.max(-F::one()).min(F::one()),
+            Activation::Square => x * x,
+            Activation::Cube => x * x * x,
+        }
+    }
+
+    /// Name of the activation function, the same accepted by `Activation::from_str`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Activation::Identity => "identity",
+            Activation::Sigmoid => "sigmoid",
+            Activation::Tanh => "tanh",
+            Activation::Relu => "relu",
+            Activation::Sin => "sin",
+            Activation::Gauss => "gauss",
+            Activation::Abs => "abs",
+            Activation::Step => "step",
+            Activation::Softplus => "softplus",
+            Activation::Clamped => "clamped",
+            Activation::Square => "square",
+            Activation::Cube => "cube",
+        }
+    }
+}
+
+impl fmt::Display for Activation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Activation {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Activation::ALL.iter()
+            .find(|activation| activation.name() == name)
+            .copied()
+            .ok_or_else(|| format!("Unknown activation function \"{}\"", name))
+    }
+}
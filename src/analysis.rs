@@ -0,0 +1,185 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Analysis helpers for the population, to plot or inspect a run.
+
+use std::io::{self, Write};
+
+use crate::util::json;
+
+/// Position of an individual in the 2D embedding of the population
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmbeddingPoint {
+    /// Id of the species of the individual
+    pub species_id: usize,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Classical (Torgerson) multidimensional scaling in two dimensions.
+///
+/// @param distances symmetric matrix of the pairwise distances
+/// @return the coordinates of every element, in the same order as the matrix
+pub fn classical_mds(distances: &[Vec<f64>]) -> Vec<(f64, f64)> {
+    let n = distances.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    // double centering of the squared distances
+    let squared: Vec<Vec<f64>> = distances.iter()
+        .map(|row| row.iter().map(|d| d * d).collect())
+        .collect();
+    let row_means: Vec<f64> = squared.iter()
+        .map(|row| row.iter().sum::<f64>() / n as f64)
+        .collect();
+    let total_mean = row_means.iter().sum::<f64>() / n as f64;
+    let mut centered: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n)
+            .map(|j| -0.5 * (squared[i][j] - row_means[i] - row_means[j] + total_mean))
+            .collect())
+        .collect();
+
+    let (value_x, vector_x) = dominant_eigenpair(&centered);
+    for i in 0..n {
+        for j in 0..n {
+            centered[i][j] -= value_x * vector_x[i] * vector_x[j];
+        }
+    }
+    let (value_y, vector_y) = dominant_eigenpair(&centered);
+
+    let scale_x = value_x.max(0.0).sqrt();
+    let scale_y = value_y.max(0.0).sqrt();
+    vector_x.iter().zip(vector_y.iter())
+        .map(|(x, y)| (x * scale_x, y * scale_y))
+        .collect()
+}
+
+/// Largest eigenvalue (and its unit eigenvector) of a symmetric matrix, by power iteration
+fn dominant_eigenpair(matrix: &[Vec<f64>]) -> (f64, Vec<f64>) {
+    const MAX_ITERATIONS: usize = 1000;
+    const TOLERANCE: f64 = 1e-12;
+
+    let n = matrix.len();
+    // a start vector unlikely to be orthogonal to the dominant eigenvector
+    let mut vector: Vec<f64> = (0..n).map(|i| ((i + 1) as f64).sqrt()).collect();
+    let mut value = 0.0;
+    for _ in 0..MAX_ITERATIONS {
+        let product: Vec<f64> = matrix.iter()
+            .map(|row| row.iter().zip(vector.iter()).map(|(a, b)| a * b).sum())
+            .collect();
+        let norm = product.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm < TOLERANCE {
+            return (0.0, vec![0.0; n]);
+        }
+        let next: Vec<f64> = product.iter().map(|x| x / norm).collect();
+        let next_value: f64 = next.iter()
+            .zip(matrix.iter())
+            .map(|(x, row)| x * row.iter().zip(next.iter()).map(|(a, b)| a * b).sum::<f64>())
+            .sum();
+        let change: f64 = next.iter().zip(vector.iter()).map(|(a, b)| (a - b).abs()).sum();
+        let converged = change < TOLERANCE;
+        vector = next;
+        value = next_value;
+        if converged {
+            break;
+        }
+    }
+    (value, vector)
+}
+
+/// Partitions the elements in (at most) `k` clusters around medoids, minimizing the distance of every
+/// element from the medoid of its cluster. The medoids are initialized with the most central element
+/// followed by the farthest ones, then assignment and medoid update alternate until nothing changes
+/// or `max_iterations` is reached.
+///
+/// @param distances symmetric matrix of the pairwise distances
+/// @return the medoids (element indices) and the cluster (index in the medoids) of every element
+pub fn k_medoids(distances: &[Vec<f64>], k: usize, max_iterations: usize) -> (Vec<usize>, Vec<usize>) {
+    let n = distances.len();
+    if n == 0 || k == 0 {
+        return (Vec::new(), vec![0; n]);
+    }
+    // sum of the distances of element `i` from the elements of cluster `c` (all elements with None)
+    let cost = |i: usize, clusters: &[usize], c: Option<usize>| -> f64 {
+        (0..n)
+            .filter(|&j| c.is_none_or(|c| clusters[j] == c))
+            .map(|j| distances[i][j])
+            .sum()
+    };
+
+    let mut medoids = vec![(0..n)
+        .min_by(|&a, &b| cost(a, &[], None).total_cmp(&cost(b, &[], None)))
+        .unwrap()];
+    while medoids.len() < k.min(n) {
+        let farthest = (0..n)
+            .filter(|i| !medoids.contains(i))
+            .map(|i| (i, medoids.iter().map(|&m| distances[i][m]).fold(f64::INFINITY, f64::min)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        match farthest {
+            Some((i, distance)) if distance > 0.0 => medoids.push(i),
+            // only duplicates of the medoids are left
+            _ => break,
+        }
+    }
+
+    let assign = |medoids: &[usize]| -> Vec<usize> {
+        (0..n)
+            .map(|i| (0..medoids.len())
+                .min_by(|&a, &b| distances[i][medoids[a]].total_cmp(&distances[i][medoids[b]]))
+                .unwrap())
+            .collect()
+    };
+    let mut clusters = assign(&medoids);
+    for _ in 0..max_iterations {
+        let new_medoids: Vec<usize> = (0..medoids.len())
+            .map(|c| (0..n)
+                .filter(|&i| clusters[i] == c)
+                .min_by(|&a, &b| cost(a, &clusters, Some(c)).total_cmp(&cost(b, &clusters, Some(c))))
+                .unwrap_or(medoids[c]))
+            .collect();
+        if new_medoids == medoids {
+            break;
+        }
+        medoids = new_medoids;
+        clusters = assign(&medoids);
+    }
+    (medoids, clusters)
+}
+
+/// Writes the embedding as CSV with the header `species_id,x,y`
+pub fn write_embedding_csv<W: Write>(points: &[EmbeddingPoint], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "species_id,x,y")?;
+    for point in points {
+        writeln!(writer, "{},{},{}", point.species_id, point.x, point.y)?;
+    }
+    Ok(())
+}
+
+/// Writes the embedding as a JSON array of `{"species_id": .., "x": .., "y": ..}` objects.
+/// Coordinates that are not finite (e.g. from a non-metric distance) are written as `null`.
+pub fn write_embedding_json<W: Write>(points: &[EmbeddingPoint], mut writer: W) -> io::Result<()> {
+    write!(writer, "[")?;
+    for (i, point) in points.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        write!(writer, "{{\"species_id\":{},\"x\":{},\"y\":{}}}",
+               point.species_id, json::number(point.x), json::number(point.y))?;
+    }
+    writeln!(writer, "]")
+}
@@ -0,0 +1,90 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fmt::Debug;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::genome::Genome;
+
+/// Number of random genomes generated by the test suite
+const SAMPLES: usize = 20;
+
+/// Checks that `G` respects the contracts expected by the speciation engine.
+/// Panics with a description of the first violated contract.
+///
+/// Call it from a test of the crate implementing the genome:
+/// `genome_test_suite::run::<MyGenome, f64>();`
+pub fn run<G: Genome<F> + Debug, F: num::Float + Debug>() {
+    run_with_seed::<G, F>(0)
+}
+
+/// Same as `run`, with a custom seed for the random number generator
+pub fn run_with_seed<G: Genome<F> + Debug, F: num::Float + Debug>(seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let genomes: Vec<G> = (0..SAMPLES).map(|_| G::random(&mut rng)).collect();
+
+    for genome in genomes.iter() {
+        assert!(genome.fitness().is_none(), "a random genome must not be evaluated: {:?}", genome);
+        check_compatibility_and_distance(genome, genome);
+        check_serialization(genome);
+    }
+
+    for (a, b) in genomes.iter().zip(genomes.iter().skip(1)) {
+        check_compatibility_and_distance(a, b);
+
+        let child = a.reproduce(&mut rng);
+        assert!(child.fitness().is_none(), "reproduce must create an unevaluated child: {:?}", child);
+        check_compatibility_and_distance(&child, &child);
+        check_compatibility_and_distance(a, &child);
+
+        let mut child = a.crossover(b, &mut rng);
+        assert!(child.fitness().is_none(), "crossover must create an unevaluated child: {:?}", child);
+        child.mutate(&mut rng);
+        check_compatibility_and_distance(&child, &child);
+        check_compatibility_and_distance(&child, b);
+        check_serialization(&child);
+    }
+}
+
+fn check_compatibility_and_distance<G: Genome<F> + Debug, F: num::Float + Debug>(a: &G, b: &G) {
+    assert!(a.is_compatible(a), "a genome must be compatible with itself: {:?}", a);
+    assert_eq!(a.is_compatible(b), b.is_compatible(a), "compatibility must be symmetric: {:?} {:?}", a, b);
+
+    match (a.distance(b), b.distance(a)) {
+        (Some(ab), Some(ba)) => {
+            assert!(ab >= F::zero(), "distance must not be negative: {:?} {:?}", a, b);
+            assert!(ab.is_finite(), "distance must be finite: {:?} {:?}", a, b);
+            assert_eq!(ab, ba, "distance must be symmetric: {:?} {:?}", a, b);
+            assert_eq!(a.distance(a), Some(F::zero()), "distance from itself must be zero: {:?}", a);
+        }
+        (None, None) => {}
+        _ => panic!("distance must be either always or never available: {:?} {:?}", a, b),
+    }
+}
+
+fn check_serialization<G: Genome<F> + Debug, F: num::Float + Debug>(genome: &G) {
+    if let Some(bytes) = genome.serialize() {
+        let restored = G::deserialize(&bytes)
+            .unwrap_or_else(|| panic!("a serialized genome must be deserializable: {:?}", genome));
+        assert_eq!(restored.serialize(), Some(bytes), "serialization must round trip: {:?}", genome);
+        if let Some(distance) = genome.distance(&restored) {
+            assert_eq!(distance, F::zero(), "a deserialized genome must be identical: {:?}", genome);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use rand::Rng;
+
+use crate::speciation::{Individual, Minimize};
+
+pub mod genome_test_suite;
+pub mod self_adaptation;
+
+/// Minimal set of operations a genome has to provide to be evolved by this crate.
+///
+/// It is meant to be implemented by external crates providing alternative genome implementations,
+/// `genome_test_suite::run` checks that an implementation respects the expected contracts.
+/// Compatibility, distance and fitness come from the `Individual` super trait.
+pub trait Genome<F: num::Float>: Individual<F> {
+    /// Creates a new random (unevaluated) genome
+    fn random<R: Rng + ?Sized>(rng: &mut R) -> Self;
+
+    /// Creates an (unevaluated) child from a single parent
+    fn reproduce<R: Rng + ?Sized>(&self, rng: &mut R) -> Self;
+
+    /// Creates an (unevaluated) child from two parents
+    fn crossover<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self;
+
+    /// Mutates the genome in place
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R);
+
+    /// Mutates the genome in place with the given strength (see `Genus::mutation_strength`), 1 being the
+    /// strength of `mutate`. Optional, ignores the strength by default.
+    fn mutate_with_strength<R: Rng + ?Sized>(&mut self, _strength: f64, rng: &mut R) {
+        self.mutate(rng)
+    }
+
+    /// Serializes the genome. Optional, returns None if not supported (the default).
+    fn serialize(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restores a genome serialized with `serialize`. Optional, returns None if not supported (the default).
+    fn deserialize(_bytes: &[u8]) -> Option<Self> {
+        None
+    }
+}
+
+impl<G: Genome<F>, F: num::Float> Genome<F> for Minimize<G> {
+    fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Minimize(G::random(rng))
+    }
+
+    fn reproduce<R: Rng + ?Sized>(&self, rng: &mut R) -> Self {
+        Minimize(self.0.reproduce(rng))
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        Minimize(self.0.crossover(&other.0, rng))
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.0.mutate(rng)
+    }
+
+    fn mutate_with_strength<R: Rng + ?Sized>(&mut self, strength: f64, rng: &mut R) {
+        self.0.mutate_with_strength(strength, rng)
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        self.0.serialize()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Option<Self> {
+        G::deserialize(bytes).map(Minimize)
+    }
+}
@@ -0,0 +1,85 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Self-adaptive mutation for real-valued genomes: every genome carries its own mutation step sizes
+//! (strategy parameters), which are inherited with the genes and mutated log-normally before the genes.
+//!
+//! A genome keeps a `StrategyParameters` next to its genes, calls `StrategyParameters::mutate` from
+//! `Genome::mutate` and `StrategyParameters::recombine` from `Genome::crossover`; `Genome::reproduce`
+//! inherits them by cloning.
+
+use rand::Rng;
+
+/// Sample of the standard normal distribution (Box-Muller transform)
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    // 1 - [0, 1) excludes 0 from the logarithm
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Mutation step sizes of a real-valued genome, one for every gene
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrategyParameters {
+    /// Standard deviation of the gaussian mutation of every gene
+    pub sigmas: Vec<f64>,
+    /// Lower bound of the step sizes, so that the mutation never vanishes
+    pub min_sigma: f64,
+}
+
+impl StrategyParameters {
+    /// Step sizes for `genes` genes, all starting from `initial_sigma`
+    pub fn new(genes: usize, initial_sigma: f64) -> Self {
+        Self {
+            sigmas: vec![initial_sigma; genes],
+            min_sigma: 1e-6,
+        }
+    }
+
+    /// Log-normal self-adaptation of the step sizes:
+    /// `sigma_i * exp(tau_global * N(0, 1) + tau_local * N_i(0, 1))`, with the usual learning rates
+    /// `tau_global = 1 / sqrt(2 n)` and `tau_local = 1 / sqrt(2 sqrt(n))`
+    pub fn mutate_sigmas<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let n = self.sigmas.len().max(1) as f64;
+        let tau_global = 1.0 / (2.0 * n).sqrt();
+        let tau_local = 1.0 / (2.0 * n.sqrt()).sqrt();
+        let global = tau_global * standard_normal(rng);
+        for sigma in self.sigmas.iter_mut() {
+            *sigma = (*sigma * (global + tau_local * standard_normal(rng)).exp()).max(self.min_sigma);
+        }
+    }
+
+    /// Mutates the step sizes (see `mutate_sigmas`) and then every gene with its new step size
+    ///
+    /// @param genes the genes of the genome, as many as the step sizes
+    pub fn mutate<R: Rng + ?Sized>(&mut self, genes: &mut [f64], rng: &mut R) {
+        assert_eq!(genes.len(), self.sigmas.len(), "one step size is needed for every gene");
+        self.mutate_sigmas(rng);
+        for (gene, sigma) in genes.iter_mut().zip(self.sigmas.iter()) {
+            *gene += sigma * standard_normal(rng);
+        }
+    }
+
+    /// Intermediate recombination: the child step sizes are the means of the parents' ones
+    pub fn recombine(&self, other: &Self) -> Self {
+        assert_eq!(self.sigmas.len(), other.sigmas.len(), "the parents must have the same number of step sizes");
+        Self {
+            sigmas: self.sigmas.iter().zip(other.sigmas.iter()).map(|(a, b)| (a + b) / 2.0).collect(),
+            min_sigma: self.min_sigma,
+        }
+    }
+}
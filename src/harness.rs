@@ -0,0 +1,296 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+
+use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
+use rand::SeedableRng;
+
+use crate::genome::Genome;
+use crate::population::bootstrap;
+use crate::population::checkpoint::Checkpoint;
+use crate::speciation::{Conf, Genus};
+use crate::util::json::Value;
+
+/// Identifier of the parent selection used by `ExampleRunner`
+const SELECTION: &str = "uniform_random_in_species";
+/// Identifier of the population management used by `ExampleRunner`
+const POPULATION_MANAGEMENT: &str = "keep_best";
+
+/// Ready-made configurations for `ExampleRunner`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// Close to the original NEAT: crossover, the best fifth of every species reproduces,
+    /// the best individual of every species survives and stagnating species are penalized sooner
+    Neat,
+    /// Asexual reproduction only, for genomes without a meaningful crossover
+    MutationOnly,
+    /// Slower convergence keeping more species alive: part of the crossovers between species,
+    /// half of every species reproduces and the champion is always preserved
+    Diverse,
+}
+
+impl Preset {
+    /// The configuration of the preset for a population of `total_population_size` individuals
+    pub fn conf(&self, total_population_size: usize) -> Conf {
+        let conf = Conf {
+            total_population_size,
+            ..Conf::default()
+        };
+        match self {
+            Preset::Neat => Conf {
+                survival_threshold: 0.2,
+                elitism: 1,
+                species_max_stagnation: 15,
+                ..conf
+            },
+            Preset::MutationOnly => Conf {
+                crossover_probability: 0.0,
+                elitism: 1,
+                ..conf
+            },
+            Preset::Diverse => Conf {
+                crossover_probability: 0.75,
+                interspecies_mating_rate: 0.05,
+                survival_threshold: 0.5,
+                preserve_champion: true,
+                ..conf
+            },
+        }
+    }
+}
+
+/// Where and how often `ExampleRunner` saves the population
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpointing {
+    /// File of the checkpoint, overwritten by every new checkpoint
+    pub path: PathBuf,
+    /// A checkpoint is saved every `interval` generations (and at the end of the run)
+    pub interval: usize,
+}
+
+/// Event of a run, given to the report function of `ExampleRunner::run`
+pub enum Report<'a, G: Genome<F>, F: num::Float> {
+    /// The run starts (or resumes), with the manifest of the run (see `ExampleRunner::manifest`)
+    Start { manifest: &'a str },
+    /// The genus at the beginning of every generation, and the last one
+    Generation(&'a Genus<G, F>),
+}
+
+/// Why the evolution stopped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Termination {
+    /// An individual reached the target fitness
+    TargetFitness,
+    /// The maximum number of generations was reached
+    MaxGenerations,
+}
+
+/// Small evolution loop for genomes implementing `Genome`, used by the shipped examples
+/// and reusable in user binaries.
+///
+/// Parents are selected uniformly at random inside their species and every species keeps
+/// its best individuals among the old and the new ones. The mutations follow `Conf::mutation_schedule`.
+/// The configuration can come from a `Preset`, the population can be saved along the run and resumed.
+pub struct ExampleRunner<F: num::Float> {
+    /// Configuration of the speciation
+    pub conf: Conf,
+    /// Seed of the random number generator given to the genome operators
+    pub seed: u64,
+    /// Stop after this number of generations
+    pub max_generations: usize,
+    /// Stop as soon as an individual reaches this fitness
+    pub target_fitness: Option<F>,
+    /// Save the population along the run (see `population::checkpoint`), the genome has to support
+    /// `Genome::serialize`. None (the default) saves nothing.
+    pub checkpointing: Option<Checkpointing>,
+}
+
+/// Result of `ExampleRunner::run`
+pub struct RunOutcome<G: Genome<F>, F: num::Float> {
+    /// The genus of the last generation
+    pub genus: Genus<G, F>,
+    /// Best fitness ever evaluated
+    pub best_fitness: F,
+    /// Why the evolution stopped
+    pub termination: Termination,
+}
+
+impl<F> ExampleRunner<F>
+where
+    F: num::Float + Debug + std::iter::Sum,
+{
+    pub fn new(conf: Conf, seed: u64, max_generations: usize, target_fitness: Option<F>) -> Self {
+        Self {
+            conf,
+            seed,
+            max_generations,
+            target_fitness,
+            checkpointing: None,
+        }
+    }
+
+    /// Runner with the configuration of a preset, see `Preset::conf`
+    pub fn with_preset(preset: Preset, total_population_size: usize, seed: u64, max_generations: usize, target_fitness: Option<F>) -> Self {
+        Self::new(preset.conf(total_population_size), seed, max_generations, target_fitness)
+    }
+
+    /// Machine-readable (JSON) description of the run: crate version, genome type, seed, termination criteria,
+    /// identifiers of the strategies and the resolved configuration (see `Conf`, one field per parameter).
+    /// `run` gives it to the report function at the beginning of the run and stores it in the checkpoints,
+    /// to record which variant of the algorithm produced the results.
+    pub fn manifest<G: Genome<F>>(&self) -> String {
+        Value::object([
+            ("crate", env!("CARGO_PKG_NAME").into()),
+            ("version", env!("CARGO_PKG_VERSION").into()),
+            ("genome", std::any::type_name::<G>().into()),
+            ("seed", self.seed.into()),
+            ("max_generations", self.max_generations.into()),
+            ("target_fitness", self.target_fitness.and_then(|target_fitness| target_fitness.to_f64()).into()),
+            ("strategies", Value::object([
+                ("selection", SELECTION.into()),
+                ("population_management", POPULATION_MANAGEMENT.into()),
+                ("offspring_allocation", self.conf.offspring_rounding.id().into()),
+                ("fitness_sharing", self.conf.fitness_sharing.id().into()),
+            ])),
+            ("conf", self.conf.to_json()),
+        ]).to_string()
+    }
+
+    /// Runs the evolution.
+    ///
+    /// @param evaluate function that evaluates an individual, it has to store the fitness in the individual
+    /// and return it
+    /// @param report function receiving the events of the run, see `Report`
+    /// @return the outcome of the run, an error if a checkpoint cannot be saved
+    pub fn run<G, E, R>(&self, evaluate: E, report: R) -> io::Result<RunOutcome<G, F>>
+    where
+        G: Genome<F> + Debug,
+        E: FnMut(&mut G) -> F,
+        R: FnMut(Report<G, F>),
+    {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let initial_population = bootstrap::from_factory(self.conf.total_population_size, |_| G::random(&mut rng));
+        let mut genus: Genus<G, F> = Genus::with_seed(self.seed);
+        genus.speciate(&self.conf, initial_population.into_iter());
+        self.evolve(genus, rng, evaluate, report)
+    }
+
+    /// Resumes the evolution from a checkpoint saved by `run` (see `checkpointing`), until the same
+    /// termination criteria. The individuals are evaluated again, the random choices continue from a seed
+    /// derived from `seed` and the generation of the checkpoint, not from the state of the interrupted run.
+    ///
+    /// @return the outcome of the run, an error if the checkpoint cannot be restored or a new one cannot be saved
+    pub fn resume<G, E, R>(&self, checkpoint: &Checkpoint, evaluate: E, report: R) -> io::Result<RunOutcome<G, F>>
+    where
+        G: Genome<F> + Debug,
+        E: FnMut(&mut G) -> F,
+        R: FnMut(Report<G, F>),
+    {
+        let seed = self.seed.wrapping_add(checkpoint.generation as u64);
+        let genus = Genus::from_checkpoint(&self.conf, checkpoint, seed)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the genomes of the checkpoint cannot be restored"))?;
+        self.evolve(genus, StdRng::seed_from_u64(seed), evaluate, report)
+    }
+
+    fn evolve<G, E, R>(&self, mut genus: Genus<G, F>, rng: StdRng, mut evaluate: E, mut report: R) -> io::Result<RunOutcome<G, F>>
+    where
+        G: Genome<F> + Debug,
+        E: FnMut(&mut G) -> F,
+        R: FnMut(Report<G, F>),
+    {
+        let manifest = self.manifest::<G>();
+        report(Report::Start { manifest: &manifest });
+
+        let conf = &self.conf;
+        let rng = RefCell::new(rng);
+        let best_fitness = Cell::new(F::neg_infinity());
+        let mut evaluate = |individual: &mut G| {
+            let fitness = evaluate(individual);
+            if fitness > best_fitness.get() {
+                best_fitness.set(fitness);
+            }
+            fitness
+        };
+        let target_reached = |best: F| self.target_fitness.is_some_and(|target| best >= target);
+
+        genus.ensure_evaluated_population(&mut evaluate);
+
+        while !target_reached(best_fitness.get()) && genus.generation() < self.max_generations {
+            report(Report::Generation(&genus));
+            if self.checkpointing.as_ref().is_some_and(|checkpointing| genus.generation() % checkpointing.interval.max(1) == 0) {
+                self.save_checkpoint(&genus, &manifest)?;
+            }
+
+            let mutation_strength = genus.mutation_strength(conf);
+            let mut generated_individuals = genus.update(conf)
+                .generate_new_individuals(
+                    conf,
+                    &mut |it| it.choose(&mut *rng.borrow_mut()).unwrap(),
+                    &mut |it| {
+                        let parents = it.choose_multiple(&mut *rng.borrow_mut(), 2);
+                        (parents[0], parents[1])
+                    },
+                    &mut |parent: &G| parent.reproduce(&mut *rng.borrow_mut()),
+                    &mut |parent1: &G, parent2: &G| parent1.crossover(parent2, &mut *rng.borrow_mut()),
+                    &mut |individual: &mut G| individual.mutate_with_strength(mutation_strength, &mut *rng.borrow_mut()),
+                );
+            generated_individuals.evaluate(&mut evaluate);
+
+            genus = genus.next_generation(conf, generated_individuals, keep_best);
+        }
+        report(Report::Generation(&genus));
+        if self.checkpointing.is_some() {
+            self.save_checkpoint(&genus, &manifest)?;
+        }
+
+        let termination = if target_reached(best_fitness.get()) {
+            Termination::TargetFitness
+        } else {
+            Termination::MaxGenerations
+        };
+
+        Ok(RunOutcome {
+            genus,
+            best_fitness: best_fitness.get(),
+            termination,
+        })
+    }
+
+    /// Writes the checkpoint of the genus, with the manifest of the run, to `checkpointing.path`
+    fn save_checkpoint<G: Genome<F> + Debug>(&self, genus: &Genus<G, F>, manifest: &str) -> io::Result<()> {
+        let checkpointing = self.checkpointing.as_ref().unwrap();
+        let checkpoint = genus.checkpoint(manifest)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "checkpointing requires Genome::serialize"))?;
+        checkpoint.write(BufWriter::new(File::create(&checkpointing.path)?))
+    }
+}
+
+/// Population management keeping the best individuals among the new and the old ones
+fn keep_best<G: Genome<F>, F: num::Float>(new_individuals: Vec<G>, old_individuals: Vec<G>, target_population: usize) -> Vec<G> {
+    let mut population: Vec<G> = new_individuals.into_iter()
+        .chain(old_individuals)
+        .collect();
+    population.sort_by(|a, b| b.fitness().partial_cmp(&a.fitness()).unwrap_or(std::cmp::Ordering::Equal));
+    population.truncate(target_population);
+    population
+}
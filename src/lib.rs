@@ -14,6 +14,12 @@
  * You should have received a copy of the GNU General Public License
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
+pub mod activations;
+pub mod analysis;
+pub mod genome;
+pub mod harness;
+pub mod population;
+pub mod selection;
 pub mod speciation;
 mod util;
 
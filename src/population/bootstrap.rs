@@ -0,0 +1,96 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// Creates `size` individuals from the factory.
+/// The factory receives the id of the individual to create, ids are unique and go from `0` to `size - 1`.
+pub fn from_factory<I, Factory>(size: usize, factory: Factory) -> Vec<I>
+    where Factory: FnMut(usize) -> I
+{
+    (0..size).map(factory).collect()
+}
+
+/// Creates `size` individuals from the factory, rejecting the candidates closer than `min_distance`
+/// to an individual already in the population (rejection sampling).
+///
+/// Ids are unique and go from `0` to `size - 1`, a rejected candidate does not consume its id.
+/// If after `max_attempts` candidates no one was far enough, the last candidate is accepted anyway,
+/// so the function always terminates.
+///
+/// @param size number of individuals to create
+/// @param min_distance minimum distance between any pair of individuals
+/// @param max_attempts maximum number of candidates generated for each individual (at least 1)
+/// @param factory function creating a new candidate with the given id
+/// @param distance function measuring the distance between two individuals
+pub fn from_factory_spread<I, T, Factory, Distance>(size: usize,
+                                                    min_distance: T,
+                                                    max_attempts: usize,
+                                                    mut factory: Factory,
+                                                    mut distance: Distance) -> Vec<I>
+    where
+        T: PartialOrd,
+        Factory: FnMut(usize) -> I,
+        Distance: FnMut(&I, &I) -> T,
+{
+    assert!(max_attempts > 0);
+    let mut population: Vec<I> = Vec::with_capacity(size);
+
+    while population.len() < size {
+        let id = population.len();
+        let mut candidate = factory(id);
+        for _ in 1..max_attempts {
+            let far_enough = population.iter()
+                .all(|individual| distance(individual, &candidate) >= min_distance);
+            if far_enough {
+                break;
+            }
+            candidate = factory(id);
+        }
+        population.push(candidate);
+    }
+
+    population
+}
+
+/// Creates `size` individuals from a seed genome.
+/// The first individual is a plain copy of the seed, all the others are copies mutated `mutations` times.
+///
+/// @param size number of individuals to create
+/// @param seed the individual all the population is derived from
+/// @param mutations number of mutations applied to each copy
+/// @param clone_with_id function that copies the seed assigning it the given id (unique, from `0` to `size - 1`)
+/// @param mutate function that mutates an individual
+pub fn from_seed<I, CloneF, MutateF>(size: usize,
+                                     seed: &I,
+                                     mutations: usize,
+                                     mut clone_with_id: CloneF,
+                                     mut mutate: MutateF) -> Vec<I>
+    where
+        CloneF: FnMut(&I, usize) -> I,
+        MutateF: FnMut(&mut I),
+{
+    (0..size)
+        .map(|id| {
+            let mut individual = clone_with_id(seed, id);
+            if id > 0 {
+                for _ in 0..mutations {
+                    mutate(&mut individual);
+                }
+            }
+            individual
+        })
+        .collect()
+}
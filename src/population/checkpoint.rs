@@ -0,0 +1,126 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Checkpoint format shared by `Population` and `Genus`, to save a run and resume it later.
+//!
+//! A checkpoint is a text file: a header line, the generation, the manifest of the run
+//! (a single line, e.g. `ExampleRunner::manifest`) and one line per individual with the id of
+//! its species (`-` without speciation) and its genome serialized by `Genome::serialize` in hex:
+//!
+//! ```text
+//! rustneat-checkpoint 1
+//! generation 12
+//! manifest {"crate":"rustneat",...}
+//! individual 3 0a1b2c
+//! individual - 0a1b2d
+//! ```
+//!
+//! The fitness is not stored, the restored individuals have to be evaluated again.
+
+use std::io::{self, BufRead, Write};
+
+const HEADER: &str = "rustneat-checkpoint 1";
+
+/// Serialized individual of a `Checkpoint`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckpointIndividual {
+    /// Id of the species of the individual, None for a population without speciation
+    pub species_id: Option<usize>,
+    /// The genome, see `Genome::serialize`
+    pub genome: Vec<u8>,
+}
+
+/// Snapshot of the individuals of a run
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Generation of the snapshot
+    pub generation: usize,
+    /// Description of the run that produced the snapshot, it must fit in one line
+    pub manifest: String,
+    pub individuals: Vec<CheckpointIndividual>,
+}
+
+impl Checkpoint {
+    /// Writes the checkpoint in the text format described in the module documentation
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        if self.manifest.contains('\n') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the checkpoint manifest must fit in one line"));
+        }
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "generation {}", self.generation)?;
+        writeln!(writer, "manifest {}", self.manifest)?;
+        for individual in &self.individuals {
+            let species_id = match individual.species_id {
+                Some(species_id) => species_id.to_string(),
+                None => "-".to_string(),
+            };
+            let genome: String = individual.genome.iter().map(|byte| format!("{:02x}", byte)).collect();
+            writeln!(writer, "individual {} {}", species_id, genome)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a checkpoint written by `write`
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut lines = reader.lines();
+        let mut next_line = |what: &str| -> io::Result<String> {
+            lines.next().unwrap_or_else(|| Err(invalid_data(format!("missing the checkpoint {}", what))))
+        };
+
+        if next_line("header")? != HEADER {
+            return Err(invalid_data("not a checkpoint, or of an unknown version".to_string()));
+        }
+        let generation = next_line("generation")?
+            .strip_prefix("generation ")
+            .and_then(|generation| generation.parse().ok())
+            .ok_or_else(|| invalid_data("invalid checkpoint generation".to_string()))?;
+        let manifest = next_line("manifest")?
+            .strip_prefix("manifest ")
+            .ok_or_else(|| invalid_data("invalid checkpoint manifest".to_string()))?
+            .to_string();
+
+        let mut individuals = Vec::new();
+        for line in lines {
+            let line = line?;
+            individuals.push(parse_individual(&line)
+                .ok_or_else(|| invalid_data(format!("invalid checkpoint individual \"{}\"", line)))?);
+        }
+
+        Ok(Self { generation, manifest, individuals })
+    }
+}
+
+fn parse_individual(line: &str) -> Option<CheckpointIndividual> {
+    let mut fields = line.strip_prefix("individual ")?.split(' ');
+    let species_id = match fields.next()? {
+        "-" => None,
+        species_id => Some(species_id.parse().ok()?),
+    };
+    let hex = fields.next()?;
+    if fields.next().is_some() || hex.len() % 2 != 0 {
+        return None;
+    }
+    let genome = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some(CheckpointIndividual { species_id, genome })
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
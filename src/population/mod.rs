@@ -0,0 +1,159 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::iter::FromIterator;
+use std::slice::{Iter, IterMut};
+
+use crate::genome::Genome;
+use crate::speciation::Individual;
+
+pub mod bootstrap;
+pub mod checkpoint;
+
+use checkpoint::{Checkpoint, CheckpointIndividual};
+
+/// A set of individuals without speciation, shared by the algorithms of the crate.
+/// `Genus::from_population` speciates it and `Genus::into_population` gives it back,
+/// both save and restore their individuals with the same `checkpoint` format.
+#[derive(Clone, Debug, Default)]
+pub struct Population<I> {
+    individuals: Vec<I>,
+}
+
+impl<I> Population<I> {
+    pub fn new() -> Self {
+        Self { individuals: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.individuals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.individuals.is_empty()
+    }
+
+    pub fn push(&mut self, individual: I) {
+        self.individuals.push(individual)
+    }
+
+    pub fn iter(&self) -> Iter<'_, I> {
+        self.individuals.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, I> {
+        self.individuals.iter_mut()
+    }
+
+    pub fn into_vec(self) -> Vec<I> {
+        self.individuals
+    }
+
+    /// The individual with the best fitness, None if no individual is evaluated
+    pub fn best<F: num::Float>(&self) -> Option<&I> where I: Individual<F> {
+        self.individuals.iter()
+            .filter(|individual| individual.fitness().is_some())
+            .max_by(|a, b| a.fitness().partial_cmp(&b.fitness()).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    /// Evaluates the individuals without fitness.
+    ///
+    /// @param evaluate function that evaluates an individual, it has to store the fitness in the individual
+    /// and return it
+    /// @return the number of evaluated individuals
+    pub fn evaluate_missing<F, E>(&mut self, mut evaluate: E) -> usize
+        where F: num::Float, I: Individual<F>, E: FnMut(&mut I) -> F
+    {
+        let mut evaluated = 0;
+        for individual in self.individuals.iter_mut().filter(|individual| individual.fitness().is_none()) {
+            evaluate_individual(individual, &mut evaluate);
+            evaluated += 1;
+        }
+        evaluated
+    }
+
+    /// Serializes every individual with `Genome::serialize`, None if the genome does not support it
+    pub fn serialize<F: num::Float>(&self) -> Option<Vec<Vec<u8>>> where I: Genome<F> {
+        self.individuals.iter()
+            .map(|individual| individual.serialize())
+            .collect()
+    }
+
+    /// Restores a population serialized with `serialize`, None if any genome cannot be restored
+    pub fn deserialize<F: num::Float>(serialized: &[Vec<u8>]) -> Option<Self> where I: Genome<F> {
+        serialized.iter()
+            .map(|bytes| I::deserialize(bytes))
+            .collect()
+    }
+
+    /// Snapshot of the population (see `checkpoint`), None if the genome does not support serialization
+    pub fn checkpoint<F: num::Float>(&self, generation: usize, manifest: &str) -> Option<Checkpoint> where I: Genome<F> {
+        let individuals = self.serialize()?.into_iter()
+            .map(|genome| CheckpointIndividual { species_id: None, genome })
+            .collect();
+        Some(Checkpoint { generation, manifest: manifest.to_string(), individuals })
+    }
+
+    /// Restores the individuals of a checkpoint (ignoring their species), None if any genome cannot be restored
+    pub fn from_checkpoint<F: num::Float>(checkpoint: &Checkpoint) -> Option<Self> where I: Genome<F> {
+        checkpoint.individuals.iter()
+            .map(|individual| I::deserialize(&individual.genome))
+            .collect()
+    }
+}
+
+/// Evaluates an individual, checking that `evaluate` stored the returned fitness in the individual.
+/// Shared by all the evaluation functions of the crate, a NaN fitness is accepted as long as it is stored.
+pub fn evaluate_individual<I, F, E>(individual: &mut I, evaluate: &mut E) -> F
+    where F: num::Float, I: Individual<F>, E: FnMut(&mut I) -> F
+{
+    let fitness = evaluate(individual);
+    let stored = individual.fitness().expect("the evaluation has to store the fitness in the individual");
+    assert!(stored == fitness || (stored.is_nan() && fitness.is_nan()),
+            "the evaluation has to store the fitness it returns in the individual");
+    fitness
+}
+
+impl<I> From<Vec<I>> for Population<I> {
+    fn from(individuals: Vec<I>) -> Self {
+        Self { individuals }
+    }
+}
+
+impl<I> FromIterator<I> for Population<I> {
+    fn from_iter<It: IntoIterator<Item=I>>(iter: It) -> Self {
+        Self { individuals: iter.into_iter().collect() }
+    }
+}
+
+impl<I> IntoIterator for Population<I> {
+    type Item = I;
+    type IntoIter = std::vec::IntoIter<I>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.individuals.into_iter()
+    }
+}
+
+impl<'a, I> IntoIterator for &'a Population<I> {
+    type Item = &'a I;
+    type IntoIter = Iter<'a, I>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.individuals.iter()
+    }
+}
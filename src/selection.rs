@@ -0,0 +1,409 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Ready-made parent selection schemes for the `selection` and `parent_selection` closures of
+//! `Genus::generate_new_individuals`, e.g.
+//! `&mut |it| Tournament::new(3).select(it, &mut rng)` and
+//! `&mut |it| Tournament::new(3).select_parents(it, &mut rng)`.
+//!
+//! `Lexicase` works on the per-case scores of `Individual::case_scores` (see `GenusSeed::evaluate_cases`).
+
+use std::cmp::Ordering;
+
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::Rng;
+
+use crate::speciation::{Individual, SpeciesIter};
+
+/// A parent selection scheme over the candidates offered by the selection closures
+pub trait Selector<F: num::Float> {
+    /// Chooses one of the `candidates` (never empty)
+    ///
+    /// @return the index of the chosen candidate
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize;
+
+    /// Chooses one individual, to be used in the `selection` closure
+    fn select<'a, I, It, R>(&self, individuals: It, rng: &mut R) -> &'a I
+    where
+        I: 'a + Individual<F>,
+        It: Iterator<Item=&'a I>,
+        R: Rng + ?Sized,
+    {
+        let candidates: Vec<&I> = individuals.collect();
+        assert!(!candidates.is_empty(), "no individual to select from");
+        candidates[self.select_index(&candidates, rng)]
+    }
+
+    /// Chooses two parents (independently), to be used in the `parent_selection` closure
+    fn select_parents<'a, I, It, R>(&self, individuals: It, rng: &mut R) -> (&'a I, &'a I)
+    where
+        I: 'a + Individual<F>,
+        It: Iterator<Item=&'a I>,
+        R: Rng + ?Sized,
+    {
+        let candidates: Vec<&I> = individuals.collect();
+        assert!(!candidates.is_empty(), "no individual to select from");
+        let first = self.select_index(&candidates, rng);
+        let second = self.select_index(&candidates, rng);
+        (candidates[first], candidates[second])
+    }
+}
+
+/// Wrapper of a selection scheme guaranteeing two different parents (two different candidates, not
+/// necessarily different genomes) whenever there is more than one candidate: the second parent is
+/// chosen by the wrapped scheme among the candidates left after the first one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DistinctParents<S>(pub S);
+
+impl<F: num::Float, S: Selector<F>> Selector<F> for DistinctParents<S> {
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize {
+        self.0.select_index(candidates, rng)
+    }
+
+    fn select_parents<'a, I, It, R>(&self, individuals: It, rng: &mut R) -> (&'a I, &'a I)
+    where
+        I: 'a + Individual<F>,
+        It: Iterator<Item=&'a I>,
+        R: Rng + ?Sized,
+    {
+        let mut candidates: Vec<&I> = individuals.collect();
+        assert!(!candidates.is_empty(), "no individual to select from");
+        let first = candidates.swap_remove(self.0.select_index(&candidates, rng));
+        if candidates.is_empty() {
+            return (first, first);
+        }
+        let second = candidates[self.0.select_index(&candidates, rng)];
+        (first, second)
+    }
+}
+
+/// Orders the individuals by fitness, the individuals without fitness are the worst
+fn compare_fitness<I: Individual<F>, F: num::Float>(a: &I, b: &I) -> Ordering {
+    a.fitness().partial_cmp(&b.fitness()).unwrap_or(Ordering::Equal)
+}
+
+/// Tournament selection: the best of `size` random candidates wins
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tournament {
+    /// Number of candidates taking part in every tournament
+    pub size: usize,
+    /// If the same candidate can be drawn more than once in a tournament. Without replacement the
+    /// tournament is never larger than the number of candidates.
+    pub replacement: bool,
+}
+
+impl Tournament {
+    /// Tournament of `size` candidates drawn without replacement
+    pub fn new(size: usize) -> Self {
+        Self { size, replacement: false }
+    }
+}
+
+impl<F: num::Float> Selector<F> for Tournament {
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize {
+        let size = self.size.max(1);
+        let compare = |a: &usize, b: &usize| compare_fitness(candidates[*a], candidates[*b]);
+        if self.replacement {
+            (0..size).map(|_| rng.gen_range(0..candidates.len())).max_by(compare).unwrap()
+        } else {
+            (0..candidates.len()).choose_multiple(rng, size).into_iter().max_by(compare).unwrap()
+        }
+    }
+}
+
+/// Weight used by the fitness-proportional selections: negative and missing (NaN) weights count as zero
+fn selection_weight<F: num::Float>(weight: F) -> f64 {
+    if weight > F::zero() { weight.to_f64().unwrap_or(0.0) } else { 0.0 }
+}
+
+/// Chooses an index with probability proportional to its weight. Negative and missing (NaN) weights
+/// count as zero, when no weight is positive the choice is uniform.
+///
+/// @param weights one weight for every candidate, not empty
+pub fn roulette_index<F: num::Float, R: Rng + ?Sized>(weights: &[F], rng: &mut R) -> usize {
+    assert!(!weights.is_empty(), "no individual to select from");
+    let total: f64 = weights.iter().map(|w| selection_weight(*w)).sum();
+    if total <= 0.0 || !total.is_finite() {
+        return rng.gen_range(0..weights.len());
+    }
+    let mut pointer = rng.gen_range(0.0..total);
+    for (i, w) in weights.iter().enumerate() {
+        let w = selection_weight(*w);
+        if pointer < w {
+            return i;
+        }
+        pointer -= w;
+    }
+    // rounding errors, the last candidate with a positive weight
+    weights.iter().rposition(|w| selection_weight(*w) > 0.0).unwrap()
+}
+
+/// Roulette-wheel (fitness-proportional) selection on the fitness of the candidates,
+/// see `roulette_index` for the handling of negative, missing and zero-total fitness
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Roulette;
+
+impl Roulette {
+    /// Chooses one individual proportionally to the given weight, e.g. the adjusted fitness from
+    /// `Species::iter_with_adjusted_fitness` for a fitness-sharing aware selection
+    pub fn select_weighted<'a, I, F, It, R>(&self, individuals: It, rng: &mut R) -> &'a I
+    where
+        I: 'a,
+        F: num::Float,
+        It: Iterator<Item=(&'a I, F)>,
+        R: Rng + ?Sized,
+    {
+        let (candidates, weights): (Vec<&I>, Vec<F>) = individuals.unzip();
+        candidates[roulette_index(&weights, rng)]
+    }
+}
+
+impl<F: num::Float> Selector<F> for Roulette {
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize {
+        let weights: Vec<F> = candidates.iter()
+            .map(|individual| individual.fitness().unwrap_or(F::zero()))
+            .collect();
+        roulette_index(&weights, rng)
+    }
+}
+
+/// Fitness-proportional selection on the adjusted fitness of the species (see `Species::adjusted_fitness`),
+/// the same value the offspring are allocated by, instead of the raw fitness. Individuals without adjusted
+/// fitness count as zero, see `roulette_index` for the handling of a zero total.
+///
+/// It needs the species iterators offered by `Genus::generate_new_individuals`, e.g.
+/// `&mut |it| AdjustedFitnessProportional.select(*it, &mut rng)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdjustedFitnessProportional;
+
+impl AdjustedFitnessProportional {
+    fn weighted<'a, I: Individual<F>, F: num::Float>(individuals: SpeciesIter<'a, I, F>) -> (Vec<&'a I>, Vec<F>) {
+        individuals.with_adjusted_fitness()
+            .map(|(individual, adjusted_fitness)| (individual, adjusted_fitness.unwrap_or(F::zero())))
+            .unzip()
+    }
+
+    /// Chooses one individual, to be used in the `selection` closure
+    pub fn select<'a, I, F, R>(&self, individuals: SpeciesIter<'a, I, F>, rng: &mut R) -> &'a I
+    where
+        I: Individual<F>,
+        F: num::Float,
+        R: Rng + ?Sized,
+    {
+        let (candidates, weights) = Self::weighted(individuals);
+        candidates[roulette_index(&weights, rng)]
+    }
+
+    /// Chooses two parents (independently), to be used in the `parent_selection` closure
+    pub fn select_parents<'a, I, F, R>(&self, individuals: SpeciesIter<'a, I, F>, rng: &mut R) -> (&'a I, &'a I)
+    where
+        I: Individual<F>,
+        F: num::Float,
+        R: Rng + ?Sized,
+    {
+        let (candidates, weights) = Self::weighted(individuals);
+        (candidates[roulette_index(&weights, rng)], candidates[roulette_index(&weights, rng)])
+    }
+}
+
+/// Stochastic universal sampling: chooses `amount` indices in one pass with equally spaced pointers
+/// on the roulette wheel, so every index is chosen within one of its expected number of times.
+/// Weights are handled like in `roulette_index`.
+pub fn sus_indices<F: num::Float, R: Rng + ?Sized>(weights: &[F], amount: usize, rng: &mut R) -> Vec<usize> {
+    assert!(!weights.is_empty(), "no individual to select from");
+    let mut weights: Vec<f64> = weights.iter().map(|w| selection_weight(*w)).collect();
+    let mut total: f64 = weights.iter().sum();
+    if total <= 0.0 || !total.is_finite() {
+        weights = vec![1.0; weights.len()];
+        total = weights.len() as f64;
+    }
+    if amount == 0 {
+        return Vec::new();
+    }
+
+    let step = total / amount as f64;
+    let mut pointer = rng.gen_range(0.0..step);
+    let mut indices = Vec::with_capacity(amount);
+    let mut cumulative = 0.0;
+    for (i, w) in weights.iter().enumerate() {
+        cumulative += w;
+        while pointer < cumulative && indices.len() < amount {
+            indices.push(i);
+            pointer += step;
+        }
+    }
+    // rounding errors, the last candidate with a positive weight
+    let last = weights.iter().rposition(|w| *w > 0.0).unwrap();
+    indices.resize(amount, last);
+    indices
+}
+
+/// Stochastic universal sampling on the fitness of the candidates (see `sus_indices`),
+/// a lower variance alternative to `Roulette`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StochasticUniversalSampling;
+
+impl StochasticUniversalSampling {
+    /// Chooses `amount` individuals in one pass, e.g. all the parents of the offspring of a species
+    /// in the `batch_selection` closure of `Genus::generate_new_individuals_with_batch_selection`.
+    /// The batch is shuffled, the pointers alone would return the individuals in their original order.
+    pub fn select_batch<'a, I, F, It, R>(&self, individuals: It, amount: usize, rng: &mut R) -> Vec<&'a I>
+    where
+        I: 'a + Individual<F>,
+        F: num::Float,
+        It: Iterator<Item=&'a I>,
+        R: Rng + ?Sized,
+    {
+        let candidates: Vec<&I> = individuals.collect();
+        let weights: Vec<F> = candidates.iter()
+            .map(|individual| individual.fitness().unwrap_or(F::zero()))
+            .collect();
+        let mut batch: Vec<&I> = sus_indices(&weights, amount, rng).into_iter()
+            .map(|i| candidates[i])
+            .collect();
+        batch.shuffle(rng);
+        batch
+    }
+}
+
+impl<F: num::Float> Selector<F> for StochasticUniversalSampling {
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize {
+        Roulette.select_index(candidates, rng)
+    }
+
+    /// Both parents are chosen in the same pass
+    fn select_parents<'a, I, It, R>(&self, individuals: It, rng: &mut R) -> (&'a I, &'a I)
+    where
+        I: 'a + Individual<F>,
+        It: Iterator<Item=&'a I>,
+        R: Rng + ?Sized,
+    {
+        let parents = self.select_batch(individuals, 2, rng);
+        (parents[0], parents[1])
+    }
+}
+
+/// Indices of the candidates from the worst to the best
+fn ranking<I: Individual<F>, F: num::Float>(candidates: &[&I]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by(|a, b| compare_fitness(candidates[*a], candidates[*b]));
+    order
+}
+
+/// Linear rank selection: the probability of a candidate grows linearly with its rank,
+/// the fitness scale does not matter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearRank {
+    /// Expected number of selections of the best candidate (in a round of as many selections as
+    /// candidates), from 1 (uniform) to 2 (the worst is never chosen)
+    pub selection_pressure: f64,
+}
+
+impl<F: num::Float> Selector<F> for LinearRank {
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize {
+        let pressure = self.selection_pressure.clamp(1.0, 2.0);
+        let last = (candidates.len() - 1).max(1) as f64;
+        let mut weights = vec![0.0; candidates.len()];
+        for (rank, i) in ranking(candidates).into_iter().enumerate() {
+            weights[i] = (2.0 - pressure) + 2.0 * (pressure - 1.0) * rank as f64 / last;
+        }
+        roulette_index(&weights, rng)
+    }
+}
+
+/// Exponential rank selection: the weight of a candidate is `base^k`, with `k` the number of
+/// candidates better than it, the fitness scale does not matter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExponentialRank {
+    /// Between 0 and 1, smaller values favour the best candidates more
+    pub base: f64,
+}
+
+impl<F: num::Float> Selector<F> for ExponentialRank {
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize {
+        let mut weights = vec![0.0; candidates.len()];
+        for (k, i) in ranking(candidates).into_iter().rev().enumerate() {
+            weights[i] = self.base.powi(k.min(i32::MAX as usize) as i32);
+        }
+        roulette_index(&weights, rng)
+    }
+}
+
+/// Truncation selection: uniform choice among the best `fraction` of the candidates (at least one),
+/// rounded up like `Conf::survival_threshold`.
+///
+/// The candidates offered by `Genus::generate_new_individuals` are already restricted to the survivors
+/// of `Conf::survival_threshold`, so with `fraction` 1 the choice is uniform among them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Truncation {
+    /// Fraction of the best candidates eligible, between 0 and 1
+    pub fraction: f64,
+}
+
+impl<F: num::Float> Selector<F> for Truncation {
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize {
+        let eligible = (candidates.len() as f64 * self.fraction).ceil() as usize;
+        let eligible = eligible.clamp(1, candidates.len());
+        let ranking = ranking(candidates);
+        ranking[ranking.len() - 1 - rng.gen_range(0..eligible)]
+    }
+}
+
+/// Lexicase selection on the scores of the candidates on the test cases (`Individual::case_scores`):
+/// the cases are considered in random order, every case keeps only the candidates (within `epsilon` of)
+/// the best on it, until one is left or the cases are over.
+///
+/// Missing and NaN scores are the worst, the choice is uniform when no candidate has case scores.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Lexicase {
+    /// Tolerance from the best score of a case for a candidate to pass it, 0 for the exact lexicase
+    pub epsilon: f64,
+}
+
+impl<F: num::Float> Selector<F> for Lexicase {
+    fn select_index<I: Individual<F>, R: Rng + ?Sized>(&self, candidates: &[&I], rng: &mut R) -> usize {
+        let mut pool: Vec<usize> = (0..candidates.len())
+            .filter(|i| candidates[*i].case_scores().is_some())
+            .collect();
+        if pool.is_empty() {
+            return rng.gen_range(0..candidates.len());
+        }
+
+        let score = |i: usize, case: usize| -> Option<F> {
+            candidates[i].case_scores()
+                .and_then(|scores| scores.get(case).copied())
+                .filter(|score| !score.is_nan())
+        };
+        let epsilon = F::from(self.epsilon).unwrap();
+        let n_cases = pool.iter()
+            .map(|i| candidates[*i].case_scores().unwrap().len())
+            .max().unwrap();
+        let mut cases: Vec<usize> = (0..n_cases).collect();
+        cases.shuffle(rng);
+
+        for case in cases {
+            if pool.len() <= 1 {
+                break;
+            }
+            let best = pool.iter().filter_map(|i| score(*i, case)).reduce(F::max);
+            if let Some(best) = best {
+                pool.retain(|i| score(*i, case).is_some_and(|score| score >= best - epsilon));
+            }
+        }
+        *pool.choose(rng).unwrap()
+    }
+}
@@ -53,4 +53,10 @@ impl Age {
     pub fn reset_evaluations(&mut self) {
         self.evaluations = 0;
     }
+}
+
+impl Default for Age {
+    fn default() -> Self {
+        Self::new()
+    }
 }
\ No newline at end of file
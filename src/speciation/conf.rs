@@ -15,11 +15,324 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::fmt;
+use std::sync::Arc;
+
+use crate::speciation::Age;
+use crate::util::json::Value;
+
+/// Function of the species age and the (shared) fitness of an individual returning the adjusted fitness,
+/// see `Conf::age_fitness_adjustment`
+pub type AgeFitnessAdjustment = Arc<dyn Fn(&Age, f64) -> f64 + Send + Sync>;
+
+/// Number of evaluation episodes (trials) given to each new individual.
+/// The budget of a new individual is decided by the species it was generated from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrialBudget {
+    /// Every individual gets the same number of trials
+    Fixed(usize),
+    /// The offspring of the best species get `max` trials, the offspring of the worst species get `min`,
+    /// the others are linearly interpolated on the species rank (by best fitness)
+    SpeciesRank { min: usize, max: usize },
+    /// The offspring of species that just improved get `max` trials, the budget linearly decreases
+    /// down to `min` as the species reaches `species_max_stagnation`
+    SpeciesStagnation { min: usize, max: usize },
+}
+
+/// How the representative of a species is chosen when the species is cloned for the next generation.
+/// The representative is the individual new candidates are compared to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepresentativePolicy {
+    /// The first individual of the species
+    First,
+    /// A random individual of the species
+    Random,
+    /// The individual with the best fitness
+    BestFitness,
+    /// The individual with the smallest total distance from the others (requires `Individual::distance`,
+    /// falls back to `First` otherwise)
+    Medoid,
+}
+
+/// How `Genus::speciate` groups the individuals into species
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpeciationStrategy {
+    /// Every individual joins the first species with a compatible representative, a new species is
+    /// created when none is found
+    #[default]
+    FirstMatch,
+    /// Every individual joins the compatible species with the nearest representative (requires
+    /// `Individual::distance`, falls back to `FirstMatch` otherwise), a new species is created when none is found
+    BestMatch,
+    /// The population is partitioned in `species` clusters around medoids by pairwise distance
+    /// (see `analysis::k_medoids`), the medoids become the representatives. The compatibility
+    /// threshold is not used. Requires `Individual::distance`, falls back to `FirstMatch` otherwise
+    /// (reported by `Genus::speciation_fallback`).
+    KMedoids { species: usize, max_iterations: usize },
+}
+
+/// What to do with the offspring not compatible with the species of their parents (the orphans)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrphanPolicy {
+    /// The orphan joins a compatible species of the next generation, a new species is created when none is found
+    #[default]
+    NewSpecies,
+    /// The orphan joins a compatible species of the next generation, or the species (other than the
+    /// one of its parents) with the nearest representative. A new species is created only when the
+    /// distance is not available (see `Individual::distance`).
+    NearestSpecies,
+    /// The orphan is discarded and re-generated by `Genus::generate_new_individuals` up to `attempts` times,
+    /// the last attempt is handled like `NewSpecies`
+    Regenerate { attempts: usize },
+}
+
+/// How the ids of new species are chosen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpeciesIdPolicy {
+    /// Every new species gets a new id, ids are never reused
+    #[default]
+    Monotonic,
+    /// New species get the smallest id not used by a living (or locked) species, reusing the ids
+    /// of the extinct species. The lineage of a reused id (see `Genus::lineage`) is replaced.
+    ReuseExtinct,
+}
+
+/// What to do when computing the adjusted fitness of a species where no individual has been evaluated.
+/// Call `Genus::ensure_evaluated_population` before `Genus::update` to force the evaluation of every individual.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnevaluatedSpeciesPolicy {
+    /// The missing fitnesses are treated as zero fitness
+    TreatAsZero,
+    /// Gives the species zero adjusted fitness (so it gets no offspring), its age and stagnation information
+    /// are not updated. The skipped species are reported by `Genus::skipped_species` and `GenusSeed::skipped_species`.
+    Skip,
+    /// Panics
+    Panic,
+}
+
+/// What to do with the species that did not improve for more than `Conf::species_max_stagnation` generations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StagnationPolicy {
+    /// Their fitness is multiplied by 1e-7, the best species is exempt
+    Penalize,
+    /// They are removed from the genus and their offspring go to the remaining species.
+    /// The `protected` best species (by best fitness, at least one) and the locked species are never removed.
+    Remove { protected: usize },
+}
+
+/// What to do when the adjusted fitness of the population collapses (zero, not finite or too small to be
+/// represented, e.g. when every species gets the stagnation penalty) and offspring cannot be allocated by fitness
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FitnessCollapsePolicy {
+    /// Panics
+    Panic,
+    /// The offspring are allocated proportionally to the size of the species
+    #[default]
+    ProportionalToSize,
+    /// The stagnation of every species is reset and the adjusted fitness recomputed,
+    /// falls back to `ProportionalToSize` if it still collapses
+    ResetStagnation,
+}
+
+/// How the fractional offspring shares of the species are rounded to integer amounts.
+/// If the rounded amounts do not sum up to the number of offspring, the best species gets the
+/// missing ones and the worst species lose the excess.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum OffspringRounding {
+    /// Every share is rounded down and all the missing offspring go to the best species
+    #[default]
+    FloorBestSpecies,
+    /// Every share is rounded down and the missing offspring go, one each, to the species
+    /// with the largest fractional remainders
+    LargestRemainder,
+    /// Every share is rounded down and the missing offspring go, one each, to species sampled
+    /// with probability proportional to their fractional remainder
+    StochasticRemainder,
+    /// Every share is rounded to the nearest integer, halves to the even one
+    RoundHalfEven,
+    /// Custom strategy, it receives the shares of the species (0 for locked species) and the number of
+    /// offspring to allocate and returns the amount of every species. The amounts have to be one per species
+    /// and sum up to the number of offspring, otherwise `Genus::generate_new_individuals` panics.
+    Custom(fn(&[f64], usize) -> Vec<usize>),
+}
+
+/// Parameters of the mass extinction events, see `Conf::mass_extinction`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MassExtinction {
+    /// Number of generations without improvement of the best fitness of the genus that trigger the extinction
+    pub stagnation: usize,
+    /// Number of best species (by best fitness, at least one) surviving the extinction with their champion
+    pub surviving_species: usize,
+}
+
+/// Parameters of the hypermutation, see `Conf::hypermutation`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hypermutation {
+    /// Number of generations without improvement of the best fitness of the genus that trigger the hypermutation
+    pub stagnation: usize,
+    /// Multiplier of the mutation strength during the hypermutation (finite and at least 1)
+    pub factor: f64,
+    /// After an improvement the multiplier is multiplied by this rate every generation, down to 1
+    /// (between 0 and 1, 0 ends the hypermutation immediately)
+    pub annealing_rate: f64,
+}
+
+/// How to handle individuals with a negative fitness when computing the adjusted fitness
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NegativeFitnessPolicy {
+    /// Negative fitness is an error: `Genus::update` panics and `Genus::try_update` returns the error
+    /// (unless `AdjustedFitnessMode::Rank` is used)
+    #[default]
+    Reject,
+    /// All the fitnesses are shifted by the minimum fitness of the genus, when it is negative,
+    /// before computing the adjusted fitness. Stagnation is still tracked on the raw fitness.
+    ShiftByMinimum,
+}
+
+/// Which value of the individuals is shared inside the species to compute the adjusted fitness
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AdjustedFitnessMode {
+    /// The fitness (see `NegativeFitnessPolicy`)
+    #[default]
+    Raw,
+    /// The rank of the fitness in the genus: 1 for the worst fitness up to the number of distinct fitness
+    /// values for the best. It does not depend on the fitness scale and allows negative fitness.
+    Rank,
+    /// Boltzmann scaling: `exp(fitness / temperature)` (normalized by the best fitness), allows negative fitness.
+    /// The temperature starts from `initial_temperature` and is multiplied by `cooling_rate` every generation,
+    /// down to `min_temperature`: a high temperature flattens the differences (exploration),
+    /// a low temperature amplifies them (exploitation).
+    Boltzmann { initial_temperature: f64, cooling_rate: f64, min_temperature: f64 },
+}
+
+impl AdjustedFitnessMode {
+    /// Temperature of the Boltzmann scaling at `generation`, None for the other modes
+    pub fn temperature(&self, generation: usize) -> Option<f64> {
+        match *self {
+            AdjustedFitnessMode::Boltzmann { initial_temperature, cooling_rate, min_temperature } => {
+                let temperature = initial_temperature * cooling_rate.powi(generation.min(i32::MAX as usize) as i32);
+                Some(temperature.max(min_temperature))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Per-generation schedule of the mutation strength, see `Genus::mutation_strength`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MutationSchedule {
+    /// Always 1
+    #[default]
+    Constant,
+    /// From `initial` to `last` linearly in `generations` generations, then `last`
+    LinearDecay { initial: f64, last: f64, generations: usize },
+    /// From `initial`, multiplied by `rate` every generation, down to `min`
+    ExponentialDecay { initial: f64, rate: f64, min: f64 },
+    /// Oscillates (cosine) between `max` at the generations multiple of `period` and `min` halfway
+    Cyclical { min: f64, max: f64, period: usize },
+}
+
+impl MutationSchedule {
+    /// Mutation strength at `generation`
+    pub fn strength(&self, generation: usize) -> f64 {
+        match *self {
+            MutationSchedule::Constant => 1.0,
+            MutationSchedule::LinearDecay { initial, last, generations } => {
+                if generation >= generations {
+                    last
+                } else {
+                    initial + (last - initial) * generation as f64 / generations as f64
+                }
+            }
+            MutationSchedule::ExponentialDecay { initial, rate, min } => {
+                (initial * rate.powi(generation.min(i32::MAX as usize) as i32)).max(min)
+            }
+            MutationSchedule::Cyclical { min, max, period } => {
+                let phase = (generation % period.max(1)) as f64 / period.max(1) as f64;
+                min + (max - min) * (1.0 + (2.0 * std::f64::consts::PI * phase).cos()) / 2.0
+            }
+        }
+    }
+}
+
+/// What the shared value of an individual is divided by to compute its adjusted fitness
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FitnessSharing {
+    /// The size of its species
+    #[default]
+    SpeciesSize,
+    /// Explicit fitness sharing (Goldberg): the niche count of the individual, the sum over the whole
+    /// population of `1 - (d / sigma)^alpha` for the individuals at distance `d < sigma` (itself included).
+    /// Requires `Individual::distance`, `Genus::try_update` returns an error otherwise.
+    Kernel { sigma: f64, alpha: f64 },
+}
+
+#[derive(Clone)]
 pub struct Conf {
     /// Total population size
     pub total_population_size: usize,
-    /// If to enable crossover
-    pub crossover: bool,
+    /// Probability that an offspring is created by crossover (when there is more than one parent
+    /// possible) rather than by asexual reproduction, each offspring decides independently.
+    /// 1 always uses crossover, 0 disables it. It must be between 0 and 1.
+    pub crossover_probability: f64,
+    /// Probability that the second parent of a crossover comes from a different species, never a locked one
+    /// (see `Genus::lock_species`). It must be between 0 and 1.
+    pub interspecies_mating_rate: f64,
+    /// Strength of the mutations along the generations, see `Genus::mutation_strength`
+    pub mutation_schedule: MutationSchedule,
+    /// Fraction of every species (the best individuals by fitness) eligible as parents, at least one
+    /// individual is always eligible. With a value below 1 the individuals of the species are kept sorted by fitness.
+    pub survival_threshold: f64,
+    /// Number of best individuals of every surviving species copied unchanged into the next generation.
+    /// They take the place of the individuals chosen by the population management.
+    pub elitism: usize,
+    /// If to always carry the best individual of the genus into the next generation unchanged,
+    /// even when its species receives no offspring
+    pub preserve_champion: bool,
+    /// Maximum distance between two compatible individuals, used when the individuals implement
+    /// `Individual::distance`. If None, only `Individual::is_compatible` is used.
+    pub compatibility_threshold: Option<f64>,
+    /// How to choose the representative of the species for the next generation
+    pub representative_policy: RepresentativePolicy,
+    /// How the individuals are grouped into species. `KMedoids` applies to `Genus::speciate` only,
+    /// `Genus::respeciate` and the orphans of the new generations use `FirstMatch` in that case.
+    pub speciation_strategy: SpeciationStrategy,
+    /// If the ids of the extinct species are reused for the new species
+    pub species_id_policy: SpeciesIdPolicy,
+    /// Maximum number of species. When it is reached, the individuals not compatible with any species
+    /// join the nearest one (see `Individual::distance`) instead of creating a new species. If None, there is no limit.
+    pub max_species: Option<usize>,
+    /// Soft capacity of a species during `Genus::speciate` and `Genus::respeciate`, as a fraction of the
+    /// individuals being assigned: the individuals that overflow a full species go to the next compatible
+    /// species (or create a new one). Full species still take individuals when `max_species` is reached.
+    /// If None, species have no capacity.
+    pub species_capacity: Option<f64>,
+    /// If to merge the species whose representatives are compatible with each other
+    /// (after speciation and at every generation)
+    pub merge_compatible_species: bool,
+    /// Maximum distance between the representatives of two species to merge them, used when the
+    /// individuals implement `Individual::distance`. If None, mutual compatibility is used.
+    pub species_merge_threshold: Option<f64>,
+    /// Species holding more than this fraction of the population are split in two, around the
+    /// representative and its most distant member. Requires `Individual::distance`. If None, species are never split.
+    pub species_split_fraction: Option<f64>,
+    /// If to fall back to asexual reproduction when the two selected parents are not compatible.
+    /// Interspecies crossovers (see `interspecies_mating_rate`) are not guarded, their parents are
+    /// incompatible by definition.
+    pub crossover_compatibility_guard: bool,
+    /// Maximum distance between the two parents of a crossover allowed by the crossover compatibility guard,
+    /// used when the individuals implement `Individual::distance`. If None, the guard checks the
+    /// speciation compatibility (see `compatibility_threshold`).
+    pub crossover_distance_threshold: Option<f64>,
+    /// What to do with the offspring not compatible with the species of their parents
+    pub orphan_policy: OrphanPolicy,
+    /// How many times an offspring genetically identical to an individual of the current population or to
+    /// another offspring is re-generated (see `Individual::genome_hash`). The last attempt is kept anyway.
+    /// 0 disables the duplicate rejection.
+    pub duplicate_offspring_retries: usize,
+    /// How many times a failing reproduction or crossover is attempted again, with the same parents, before
+    /// falling back to a clone of the (first) parent (see `Genus::generate_new_individuals_fallible`)
+    pub operator_retries: usize,
 
     // SPECIES specific parameters
 
@@ -29,45 +342,418 @@ pub struct Conf {
     pub old_age_threshold: usize,
     /// when to consider a species stagnating (inclusive)
     pub species_max_stagnation: usize,
+    /// Number of generations kept in the best fitness history of every species (see `Species::fitness_history`)
+    pub fitness_history_length: usize,
+    /// Minimum number of offspring for every species that is not stagnating (see `species_max_stagnation`),
+    /// so that young species are not wiped out by rounding. `Genus::generate_new_individuals` panics if the
+    /// population cannot fit all the quotas.
+    pub min_offspring: usize,
+    /// How the offspring shares of the species are rounded
+    pub offspring_rounding: OffspringRounding,
+    /// What to do with the stagnating species
+    pub stagnation_policy: StagnationPolicy,
+    /// What to do when the adjusted fitness of the population collapses, see `Genus::fitness_collapses`
+    pub fitness_collapse_policy: FitnessCollapsePolicy,
+    /// If set, after too many generations without improvement only the champions of the best species
+    /// survive and the population is refilled from them. Locked species are not affected,
+    /// `Genus::last_mass_extinction` reports when it happened.
+    pub mass_extinction: Option<MassExtinction>,
+    /// If set, the mutation strength (see `Genus::mutation_strength`) is boosted after too many generations
+    /// without improvement and annealed back once the best fitness improves
+    pub hypermutation: Option<Hypermutation>,
 
     /// multiplier for the fitness of young species (keep > 1)
     pub young_age_fitness_boost: f64,
     /// multiplier for the fitness of old species (keep > 0 and < 1)
     pub old_age_fitness_penalty: f64,
+    /// Function of the species age and the (shared) fitness of an individual returning the fitness after
+    /// the age adjustment. When set, it replaces `young_age_fitness_boost` and `old_age_fitness_penalty`.
+    pub age_fitness_adjustment: Option<AgeFitnessAdjustment>,
+    /// what to do with species without any evaluated individual
+    pub unevaluated_species_policy: UnevaluatedSpeciesPolicy,
+    /// Fitness used in place of a zero fitness when computing the adjusted fitness.
+    /// With 0 zero fitness stays zero, if the whole population has zero fitness the offspring are allocated
+    /// following `fitness_collapse_policy`.
+    pub zero_fitness_epsilon: f64,
+    /// How to handle negative fitness
+    pub negative_fitness_policy: NegativeFitnessPolicy,
+    /// How to compute the adjusted fitness
+    pub adjusted_fitness_mode: AdjustedFitnessMode,
+    /// how the shared value is divided among the individuals
+    pub fitness_sharing: FitnessSharing,
+
+    /// multiplier for the fitness of species whose representative is far from all the other
+    /// representatives (keep >= 1, 1 disables the bonus). Requires `Individual::distance`.
+    pub species_novelty_bonus: f64,
+    /// minimum distance from all the other representatives to receive the novelty bonus
+    pub species_novelty_distance: f64,
+
+    /// number of evaluation trials for the new individuals (see `GenusSeed::evaluate_trials`)
+    pub trial_budget: TrialBudget,
 }
 
 impl Conf {
+    /// Panics if the parameters are not valid, see `validate`
     pub fn new(
         total_population_size: usize,
-        crossover: bool,
+        crossover_probability: f64,
         young_age_threshold: usize,
         old_age_threshold: usize,
         species_max_stagnation: usize,
         young_age_fitness_boost: f64,
         old_age_fitness_penalty: f64,
     ) -> Self {
-        Self {
+        let conf = Self {
             total_population_size,
-            crossover,
+            crossover_probability,
             young_age_threshold,
             old_age_threshold,
             species_max_stagnation,
             young_age_fitness_boost,
             old_age_fitness_penalty,
+            ..Self::default()
+        };
+        if let Err(error) = conf.validate() {
+            panic!("{}", error);
+        }
+        conf
+    }
+
+    /// Checks the parameters that have to be in a given range.
+    /// `Genus::try_update` returns the error and `Genus::generate_new_individuals` panics with it.
+    pub fn validate(&self) -> Result<(), String> {
+        validate_probability("crossover_probability", self.crossover_probability)?;
+        validate_probability("interspecies_mating_rate", self.interspecies_mating_rate)?;
+        if let Some(hypermutation) = self.hypermutation {
+            if !(hypermutation.factor.is_finite() && hypermutation.factor >= 1.0) {
+                return Err(format!("Conf::hypermutation factor must be finite and at least 1, it is {}", hypermutation.factor));
+            }
+            validate_probability("hypermutation annealing_rate", hypermutation.annealing_rate)?;
+        }
+        Ok(())
+    }
+
+    /// JSON object with one field per parameter, in the order of the declaration.
+    /// The strategies are written as their identifier, or as an object with the identifier in `kind` followed
+    /// by their parameters. Functions are written as `"custom"`, their code cannot be recorded.
+    pub(crate) fn to_json(&self) -> Value {
+        Value::object([
+            ("total_population_size", self.total_population_size.into()),
+            ("crossover_probability", self.crossover_probability.into()),
+            ("interspecies_mating_rate", self.interspecies_mating_rate.into()),
+            ("mutation_schedule", self.mutation_schedule.to_json()),
+            ("survival_threshold", self.survival_threshold.into()),
+            ("elitism", self.elitism.into()),
+            ("preserve_champion", self.preserve_champion.into()),
+            ("compatibility_threshold", self.compatibility_threshold.into()),
+            ("representative_policy", self.representative_policy.to_json()),
+            ("speciation_strategy", self.speciation_strategy.to_json()),
+            ("species_id_policy", self.species_id_policy.to_json()),
+            ("max_species", self.max_species.into()),
+            ("species_capacity", self.species_capacity.into()),
+            ("merge_compatible_species", self.merge_compatible_species.into()),
+            ("species_merge_threshold", self.species_merge_threshold.into()),
+            ("species_split_fraction", self.species_split_fraction.into()),
+            ("crossover_compatibility_guard", self.crossover_compatibility_guard.into()),
+            ("crossover_distance_threshold", self.crossover_distance_threshold.into()),
+            ("orphan_policy", self.orphan_policy.to_json()),
+            ("duplicate_offspring_retries", self.duplicate_offspring_retries.into()),
+            ("operator_retries", self.operator_retries.into()),
+            ("young_age_threshold", self.young_age_threshold.into()),
+            ("old_age_threshold", self.old_age_threshold.into()),
+            ("species_max_stagnation", self.species_max_stagnation.into()),
+            ("fitness_history_length", self.fitness_history_length.into()),
+            ("min_offspring", self.min_offspring.into()),
+            ("offspring_rounding", self.offspring_rounding.to_json()),
+            ("stagnation_policy", self.stagnation_policy.to_json()),
+            ("fitness_collapse_policy", self.fitness_collapse_policy.to_json()),
+            ("mass_extinction", self.mass_extinction.map_or(Value::Null, |mass_extinction| Value::object([
+                ("stagnation", mass_extinction.stagnation.into()),
+                ("surviving_species", mass_extinction.surviving_species.into()),
+            ]))),
+            ("hypermutation", self.hypermutation.map_or(Value::Null, |hypermutation| Value::object([
+                ("stagnation", hypermutation.stagnation.into()),
+                ("factor", hypermutation.factor.into()),
+                ("annealing_rate", hypermutation.annealing_rate.into()),
+            ]))),
+            ("young_age_fitness_boost", self.young_age_fitness_boost.into()),
+            ("old_age_fitness_penalty", self.old_age_fitness_penalty.into()),
+            ("age_fitness_adjustment", self.age_fitness_adjustment.as_ref().map(|_| "custom").into()),
+            ("unevaluated_species_policy", self.unevaluated_species_policy.to_json()),
+            ("zero_fitness_epsilon", self.zero_fitness_epsilon.into()),
+            ("negative_fitness_policy", self.negative_fitness_policy.to_json()),
+            ("adjusted_fitness_mode", self.adjusted_fitness_mode.to_json()),
+            ("fitness_sharing", self.fitness_sharing.to_json()),
+            ("species_novelty_bonus", self.species_novelty_bonus.into()),
+            ("species_novelty_distance", self.species_novelty_distance.into()),
+            ("trial_budget", self.trial_budget.to_json()),
+        ])
+    }
+}
+
+impl TrialBudget {
+    fn to_json(self) -> Value {
+        match self {
+            TrialBudget::Fixed(trials) => Value::strategy("fixed", [("trials", trials.into())]),
+            TrialBudget::SpeciesRank { min, max } => Value::strategy("species_rank", [("min", min.into()), ("max", max.into())]),
+            TrialBudget::SpeciesStagnation { min, max } => Value::strategy("species_stagnation", [("min", min.into()), ("max", max.into())]),
+        }
+    }
+}
+
+impl RepresentativePolicy {
+    fn to_json(self) -> Value {
+        Value::id(match self {
+            RepresentativePolicy::First => "first",
+            RepresentativePolicy::Random => "random",
+            RepresentativePolicy::BestFitness => "best_fitness",
+            RepresentativePolicy::Medoid => "medoid",
+        })
+    }
+}
+
+impl SpeciationStrategy {
+    fn to_json(self) -> Value {
+        match self {
+            SpeciationStrategy::FirstMatch => Value::id("first_match"),
+            SpeciationStrategy::BestMatch => Value::id("best_match"),
+            SpeciationStrategy::KMedoids { species, max_iterations } =>
+                Value::strategy("k_medoids", [("species", species.into()), ("max_iterations", max_iterations.into())]),
+        }
+    }
+}
+
+impl OrphanPolicy {
+    fn to_json(self) -> Value {
+        match self {
+            OrphanPolicy::NewSpecies => Value::id("new_species"),
+            OrphanPolicy::NearestSpecies => Value::id("nearest_species"),
+            OrphanPolicy::Regenerate { attempts } => Value::strategy("regenerate", [("attempts", attempts.into())]),
+        }
+    }
+}
+
+impl SpeciesIdPolicy {
+    fn to_json(self) -> Value {
+        Value::id(match self {
+            SpeciesIdPolicy::Monotonic => "monotonic",
+            SpeciesIdPolicy::ReuseExtinct => "reuse_extinct",
+        })
+    }
+}
+
+impl UnevaluatedSpeciesPolicy {
+    fn to_json(self) -> Value {
+        Value::id(match self {
+            UnevaluatedSpeciesPolicy::TreatAsZero => "treat_as_zero",
+            UnevaluatedSpeciesPolicy::Skip => "skip",
+            UnevaluatedSpeciesPolicy::Panic => "panic",
+        })
+    }
+}
+
+impl StagnationPolicy {
+    fn to_json(self) -> Value {
+        match self {
+            StagnationPolicy::Penalize => Value::id("penalize"),
+            StagnationPolicy::Remove { protected } => Value::strategy("remove", [("protected", protected.into())]),
+        }
+    }
+}
+
+impl FitnessCollapsePolicy {
+    fn to_json(self) -> Value {
+        Value::id(match self {
+            FitnessCollapsePolicy::Panic => "panic",
+            FitnessCollapsePolicy::ProportionalToSize => "proportional_to_size",
+            FitnessCollapsePolicy::ResetStagnation => "reset_stagnation",
+        })
+    }
+}
+
+impl OffspringRounding {
+    /// Identifier of the offspring allocation
+    pub(crate) fn id(&self) -> &'static str {
+        match self {
+            OffspringRounding::FloorBestSpecies => "floor_best_species",
+            OffspringRounding::LargestRemainder => "largest_remainder",
+            OffspringRounding::StochasticRemainder => "stochastic_remainder",
+            OffspringRounding::RoundHalfEven => "round_half_even",
+            OffspringRounding::Custom(_) => "custom",
+        }
+    }
+
+    fn to_json(self) -> Value {
+        Value::id(self.id())
+    }
+}
+
+impl NegativeFitnessPolicy {
+    fn to_json(self) -> Value {
+        Value::id(match self {
+            NegativeFitnessPolicy::Reject => "reject",
+            NegativeFitnessPolicy::ShiftByMinimum => "shift_by_minimum",
+        })
+    }
+}
+
+impl AdjustedFitnessMode {
+    fn to_json(self) -> Value {
+        match self {
+            AdjustedFitnessMode::Raw => Value::id("raw"),
+            AdjustedFitnessMode::Rank => Value::id("rank"),
+            AdjustedFitnessMode::Boltzmann { initial_temperature, cooling_rate, min_temperature } => Value::strategy("boltzmann", [
+                ("initial_temperature", initial_temperature.into()),
+                ("cooling_rate", cooling_rate.into()),
+                ("min_temperature", min_temperature.into()),
+            ]),
         }
     }
 }
 
+impl MutationSchedule {
+    fn to_json(self) -> Value {
+        match self {
+            MutationSchedule::Constant => Value::id("constant"),
+            MutationSchedule::LinearDecay { initial, last, generations } => Value::strategy("linear_decay", [
+                ("initial", initial.into()),
+                ("last", last.into()),
+                ("generations", generations.into()),
+            ]),
+            MutationSchedule::ExponentialDecay { initial, rate, min } => Value::strategy("exponential_decay", [
+                ("initial", initial.into()),
+                ("rate", rate.into()),
+                ("min", min.into()),
+            ]),
+            MutationSchedule::Cyclical { min, max, period } => Value::strategy("cyclical", [
+                ("min", min.into()),
+                ("max", max.into()),
+                ("period", period.into()),
+            ]),
+        }
+    }
+}
+
+impl FitnessSharing {
+    /// Identifier of the fitness sharing
+    pub(crate) fn id(&self) -> &'static str {
+        match self {
+            FitnessSharing::SpeciesSize => "species_size",
+            FitnessSharing::Kernel { .. } => "kernel",
+        }
+    }
+
+    fn to_json(self) -> Value {
+        match self {
+            FitnessSharing::SpeciesSize => Value::id(self.id()),
+            FitnessSharing::Kernel { sigma, alpha } => Value::strategy(self.id(), [("sigma", sigma.into()), ("alpha", alpha.into())]),
+        }
+    }
+}
+
+fn validate_probability(name: &str, value: f64) -> Result<(), String> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("Conf::{} must be between 0 and 1, it is {}", name, value))
+    }
+}
+
 impl Default for Conf {
     fn default() -> Self {
         Self {
             total_population_size: 100,
-            crossover: true,
+            crossover_probability: 1.0,
+            interspecies_mating_rate: 0.0,
+            mutation_schedule: MutationSchedule::default(),
+            survival_threshold: 1.0,
+            elitism: 0,
+            preserve_champion: false,
+            compatibility_threshold: None,
+            representative_policy: RepresentativePolicy::First,
+            speciation_strategy: SpeciationStrategy::default(),
+            species_id_policy: SpeciesIdPolicy::default(),
+            max_species: None,
+            species_capacity: None,
+            merge_compatible_species: false,
+            species_merge_threshold: None,
+            species_split_fraction: None,
+            crossover_compatibility_guard: false,
+            crossover_distance_threshold: None,
+            orphan_policy: OrphanPolicy::default(),
+            duplicate_offspring_retries: 0,
+            operator_retries: 3,
             young_age_threshold: 10,
             old_age_threshold: 40,
             species_max_stagnation: 400,
+            fitness_history_length: 100,
+            min_offspring: 0,
+            offspring_rounding: OffspringRounding::default(),
+            stagnation_policy: StagnationPolicy::Penalize,
+            fitness_collapse_policy: FitnessCollapsePolicy::default(),
+            mass_extinction: None,
+            hypermutation: None,
             young_age_fitness_boost: 1.1,
             old_age_fitness_penalty: 0.9,
+            age_fitness_adjustment: None,
+            unevaluated_species_policy: UnevaluatedSpeciesPolicy::TreatAsZero,
+            zero_fitness_epsilon: 0.0001,
+            negative_fitness_policy: NegativeFitnessPolicy::default(),
+            adjusted_fitness_mode: AdjustedFitnessMode::default(),
+            fitness_sharing: FitnessSharing::default(),
+            species_novelty_bonus: 1.0,
+            species_novelty_distance: 0.0,
+            trial_budget: TrialBudget::Fixed(1),
         }
     }
-}
\ No newline at end of file
+}
+
+// written by hand because `age_fitness_adjustment` is a closure
+impl fmt::Debug for Conf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Conf")
+            .field("total_population_size", &self.total_population_size)
+            .field("crossover_probability", &self.crossover_probability)
+            .field("interspecies_mating_rate", &self.interspecies_mating_rate)
+            .field("mutation_schedule", &self.mutation_schedule)
+            .field("survival_threshold", &self.survival_threshold)
+            .field("elitism", &self.elitism)
+            .field("preserve_champion", &self.preserve_champion)
+            .field("compatibility_threshold", &self.compatibility_threshold)
+            .field("representative_policy", &self.representative_policy)
+            .field("speciation_strategy", &self.speciation_strategy)
+            .field("species_id_policy", &self.species_id_policy)
+            .field("max_species", &self.max_species)
+            .field("species_capacity", &self.species_capacity)
+            .field("merge_compatible_species", &self.merge_compatible_species)
+            .field("species_merge_threshold", &self.species_merge_threshold)
+            .field("species_split_fraction", &self.species_split_fraction)
+            .field("crossover_compatibility_guard", &self.crossover_compatibility_guard)
+            .field("crossover_distance_threshold", &self.crossover_distance_threshold)
+            .field("orphan_policy", &self.orphan_policy)
+            .field("duplicate_offspring_retries", &self.duplicate_offspring_retries)
+            .field("operator_retries", &self.operator_retries)
+            .field("young_age_threshold", &self.young_age_threshold)
+            .field("old_age_threshold", &self.old_age_threshold)
+            .field("species_max_stagnation", &self.species_max_stagnation)
+            .field("fitness_history_length", &self.fitness_history_length)
+            .field("min_offspring", &self.min_offspring)
+            .field("offspring_rounding", &self.offspring_rounding)
+            .field("stagnation_policy", &self.stagnation_policy)
+            .field("fitness_collapse_policy", &self.fitness_collapse_policy)
+            .field("mass_extinction", &self.mass_extinction)
+            .field("hypermutation", &self.hypermutation)
+            .field("young_age_fitness_boost", &self.young_age_fitness_boost)
+            .field("old_age_fitness_penalty", &self.old_age_fitness_penalty)
+            .field("age_fitness_adjustment", &self.age_fitness_adjustment.as_ref().map(|_| "Fn(&Age, f64) -> f64"))
+            .field("unevaluated_species_policy", &self.unevaluated_species_policy)
+            .field("zero_fitness_epsilon", &self.zero_fitness_epsilon)
+            .field("negative_fitness_policy", &self.negative_fitness_policy)
+            .field("adjusted_fitness_mode", &self.adjusted_fitness_mode)
+            .field("fitness_sharing", &self.fitness_sharing)
+            .field("species_novelty_bonus", &self.species_novelty_bonus)
+            .field("species_novelty_distance", &self.species_novelty_distance)
+            .field("trial_budget", &self.trial_budget)
+            .finish()
+    }
+}
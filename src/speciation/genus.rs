@@ -14,12 +14,21 @@
  * You should have received a copy of the GNU General Public License 
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
-use std::cell::RefCell;
-use std::collections::HashSet;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::rc::Rc;
 
-use crate::speciation::{Conf, Individual, Species};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::analysis::{classical_mds, k_medoids, EmbeddingPoint};
+use crate::genome::Genome;
+use crate::population::{self, Population};
+use crate::population::checkpoint::{Checkpoint, CheckpointIndividual};
+
+use crate::speciation::{are_crossover_compatible, AdjustedFitnessMode, Conf, FitnessCollapsePolicy, FitnessSharing, Individual, NegativeFitnessPolicy, OffspringRounding, OperatorError, OrphanPolicy, ReproductionOperators, SpeciationStrategy, Species, StagnationPolicy, TrialBudget};
 use crate::speciation::genus_seed::GenusSeed;
 use crate::speciation::species::{RcSpecies, SpeciesIter};
 use crate::util::iterators::has_unique_elements;
@@ -27,28 +36,322 @@ use crate::util::iterators::has_unique_elements;
 use super::species_collection::SpeciesCollection;
 
 pub struct Genus<I: Individual<F>, F: num::Float> {
+    /// Index of the current generation, the first genus is generation 0
+    generation: usize,
     next_species_id: usize,
     species_collection: SpeciesCollection<I, F>,
+    /// Species (ids) excluded from reproduction, their individuals are carried over unchanged
+    locked_species: HashSet<usize>,
+    /// Best fitness found so far, updated by `update`
+    best_fitness: Option<F>,
+    /// Number of consecutive `update`s without improvement of `best_fitness`
+    generations_without_improvement: usize,
+    /// Current multiplier of the mutation strength, see `Conf::hypermutation`
+    hypermutation_factor: f64,
+    /// Generation of the last mass extinction, see `Conf::mass_extinction`
+    last_mass_extinction: Option<usize>,
+    /// Number of generations whose adjusted fitness collapsed, see `Conf::fitness_collapse_policy`
+    fitness_collapses: usize,
+    /// If the last `speciate` could not use `SpeciationStrategy::KMedoids` for lack of `Individual::distance`
+    speciation_fallback: bool,
+    /// Parent species (id) of every species created after the initial population, extinct ones included
+    species_parents: HashMap<usize, usize>,
+    /// Species (ids) skipped by the last `update` because none of their individuals is evaluated,
+    /// see `UnevaluatedSpeciesPolicy::Skip`
+    skipped_species: Vec<usize>,
+    /// Random number generator of all the internal random choices, see `with_seed`
+    rng: RefCell<StdRng>,
 }
 
 impl<I, F> Genus<I, F>
 where
-    I: 'static + Individual<F> + Debug,
-    F: 'static + num::Float + Debug + std::iter::Sum,
+    I: Individual<F> + Debug,
+    F: num::Float + Debug + std::iter::Sum,
 {
-    /// Creates a new Genus object
+    /// Creates a new Genus object, its internal random choices are seeded from the system entropy
     pub fn new() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+
+    /// Creates a new Genus object whose internal random choices are reproducible from `seed`.
+    /// For a fully reproducible run the operators have to use `fork_rng` (or their own seeded generator).
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
         Self {
+            generation: 0,
             next_species_id: 1,
             species_collection: SpeciesCollection::new(),
+            locked_species: HashSet::new(),
+            best_fitness: None,
+            generations_without_improvement: 0,
+            hypermutation_factor: 1.0,
+            last_mass_extinction: None,
+            fitness_collapses: 0,
+            speciation_fallback: false,
+            species_parents: HashMap::new(),
+            skipped_species: Vec::new(),
+            rng: RefCell::new(rng),
+        }
+    }
+
+    /// Restarts the internal random choices from `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = RefCell::new(StdRng::seed_from_u64(seed));
+    }
+
+    /// New random number generator seeded from the internal one, e.g. for the operators passed to
+    /// `generate_new_individuals`: the run stays reproducible from the seed of `with_seed`
+    pub fn fork_rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.rng.borrow_mut().gen())
+    }
+
+    /// Creates a new Genus speciating the population
+    pub fn from_population(conf: &Conf, population: Population<I>) -> Self {
+        let mut genus = Self::new();
+        genus.speciate(conf, population.into_iter());
+        genus
+    }
+
+    /// Same as `from_population`, with the internal random choices reproducible from `seed` (see `with_seed`)
+    pub fn from_population_with_seed(conf: &Conf, population: Population<I>, seed: u64) -> Self {
+        let mut genus = Self::with_seed(seed);
+        genus.speciate(conf, population.into_iter());
+        genus
+    }
+
+    /// Snapshot of the individuals with their species and of the generation (see `population::checkpoint`),
+    /// None if the genome does not support serialization
+    pub fn checkpoint(&self, manifest: &str) -> Option<Checkpoint> where I: Genome<F> {
+        let mut individuals = Vec::new();
+        for species in self.species_collection.iter() {
+            for individual in species.iter() {
+                individuals.push(CheckpointIndividual { species_id: Some(species.id), genome: individual.serialize()? });
+            }
         }
+        Some(Checkpoint { generation: self.generation, manifest: manifest.to_string(), individuals })
     }
 
-    fn build_next_generation(species_collection: SpeciesCollection<I, F>, next_species_id: usize) -> Self {
+    /// Restores a genus from a checkpoint: the individuals go back to the species with their id, the ones
+    /// without a species id (e.g. from `Population::checkpoint`) are speciated.
+    /// The rest of the history (species age, stagnation, best fitness) is not part of the checkpoint and starts over,
+    /// the individuals have to be evaluated again.
+    ///
+    /// @return the genus with the generation of the checkpoint, None if any genome cannot be restored
+    pub fn from_checkpoint(conf: &Conf, checkpoint: &Checkpoint, seed: u64) -> Option<Self> where I: Genome<F> {
+        let mut genus = Self::with_seed(seed);
+        genus.generation = checkpoint.generation;
+        let mut without_species: Vec<I> = Vec::new();
+        for entry in &checkpoint.individuals {
+            let individual = I::deserialize(&entry.genome)?;
+            match entry.species_id {
+                Some(species_id) => {
+                    match genus.species_collection.iter_mut().find(|species| species.id == species_id) {
+                        Some(species) => species.insert(individual),
+                        None => genus.species_collection.push(Species::new(individual, species_id)),
+                    }
+                    genus.next_species_id = genus.next_species_id.max(species_id + 1);
+                }
+                None => without_species.push(individual),
+            }
+        }
+        if !without_species.is_empty() {
+            genus.assign_to_species(conf, without_species.into_iter());
+        }
+        Some(genus)
+    }
+
+    /// Copies all the individuals of all the species into a population
+    pub fn to_population(&self) -> Population<I> {
+        self.iter_individuals().cloned().collect()
+    }
+
+    /// Takes all the individuals out of the species
+    pub fn into_population(mut self) -> Population<I> {
+        self.species_collection.iter_mut()
+            .flat_map(|species| species.drain_individuals().collect::<Vec<I>>())
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_next_generation(generation: usize,
+                             species_collection: SpeciesCollection<I, F>,
+                             next_species_id: usize,
+                             locked_species: HashSet<usize>,
+                             best_fitness: Option<F>,
+                             generations_without_improvement: usize,
+                             hypermutation_factor: f64,
+                             last_mass_extinction: Option<usize>,
+                             fitness_collapses: usize,
+                             speciation_fallback: bool,
+                             species_parents: HashMap<usize, usize>,
+                             rng: StdRng) -> Self {
         Self {
+            generation,
             next_species_id,
-            species_collection
+            species_collection,
+            locked_species,
+            best_fitness,
+            generations_without_improvement,
+            hypermutation_factor,
+            last_mass_extinction,
+            fitness_collapses,
+            speciation_fallback,
+            species_parents,
+            skipped_species: Vec::new(),
+            rng: RefCell::new(rng),
+        }
+    }
+
+    /// Index of the current generation. It starts from 0 and it is increased by `next_generation`.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Restores the index of the current generation (see `generation`), so that a genus restored from a
+    /// saved population continues the schedules that depend on it (`Conf::mutation_schedule`,
+    /// the temperature of `AdjustedFitnessMode::Boltzmann`)
+    pub fn set_generation(&mut self, generation: usize) {
+        self.generation = generation;
+    }
+
+    /// Mutation strength for the offspring of the current generation following `conf.mutation_schedule`,
+    /// boosted by the hypermutation (see `Conf::hypermutation`), to be passed to the mutation operator
+    /// (see `Genome::mutate_with_strength`)
+    pub fn mutation_strength(&self, conf: &Conf) -> f64 {
+        conf.mutation_schedule.strength(self.generation) * self.hypermutation_factor
+    }
+
+    /// Current multiplier of the mutation strength, 1 outside of the hypermutation. Updated by `update`.
+    pub fn hypermutation_factor(&self) -> f64 {
+        self.hypermutation_factor
+    }
+
+    /// Generation whose `update` triggered the last mass extinction (see `Conf::mass_extinction`),
+    /// None if there was none
+    pub fn last_mass_extinction(&self) -> Option<usize> {
+        self.last_mass_extinction
+    }
+
+    /// Number of generations whose offspring could not be allocated by adjusted fitness and were
+    /// allocated following `Conf::fitness_collapse_policy` instead
+    pub fn fitness_collapses(&self) -> usize {
+        self.fitness_collapses
+    }
+
+    /// True if the last `speciate` was asked for `SpeciationStrategy::KMedoids` but `Individual::distance`
+    /// is not available, so the individuals were grouped with `SpeciationStrategy::FirstMatch`
+    pub fn speciation_fallback(&self) -> bool {
+        self.speciation_fallback
+    }
+
+    /// Id the next new species will get (with `SpeciesIdPolicy::Monotonic`), to be saved with the population
+    pub fn next_species_id(&self) -> usize {
+        self.next_species_id
+    }
+
+    /// Restores the id of the next new species (see `next_species_id`), so that a genus restored from a
+    /// saved population does not give the new species ids already used before the save.
+    /// Call it before `speciate`.
+    pub fn set_next_species_id(&mut self, next_species_id: usize) {
+        self.next_species_id = next_species_id;
+    }
+
+    /// Species (ids) the last `update` gave zero adjusted fitness because none of their individuals is
+    /// evaluated (see `UnevaluatedSpeciesPolicy::Skip`)
+    pub fn skipped_species(&self) -> &[usize] {
+        &self.skipped_species
+    }
+
+    /// Number of consecutive generations (`update` calls) without improvement of the best fitness
+    pub fn generations_without_improvement(&self) -> usize {
+        self.generations_without_improvement
+    }
+
+    /// Current champion: the best individual of the best species
+    ///
+    /// @return None if no individual is evaluated
+    pub fn best_individual(&self) -> Option<&I> {
+        self.species_collection.best()
+            .and_then(|i| self.species_collection.get(i).get_best_individual())
+    }
+
+    /// Fitness of the current champion, see `best_individual`
+    pub fn best_fitness(&self) -> Option<F> {
+        self.best_individual().and_then(|individual| individual.fitness())
+    }
+
+    /// Species the founder of species `species_id` came from, None for the species of the initial population
+    /// (or unknown ids). It works for extinct species too.
+    pub fn parent_species(&self, species_id: usize) -> Option<usize> {
+        self.species_parents.get(&species_id).copied()
+    }
+
+    /// Ancestors of species `species_id`, from its parent species back to a species of the initial population.
+    /// With `SpeciesIdPolicy::ReuseExtinct` the lineage stops at the first id seen twice.
+    pub fn lineage(&self, species_id: usize) -> Vec<usize> {
+        let mut lineage = Vec::new();
+        let mut current = species_id;
+        while let Some(parent) = self.parent_species(current) {
+            if parent == species_id || lineage.contains(&parent) {
+                break;
+            }
+            lineage.push(parent);
+            current = parent;
         }
+        lineage
+    }
+
+    /// Speciation quality: mean silhouette coefficient of the individuals, see `SpeciesCollection::silhouette`.
+    /// Values near 1 mean that the species are coherent clusters, values near 0 or negative that the
+    /// compatibility threshold is not separating them.
+    ///
+    /// @param samples number of individuals sampled to compute the coefficient, None to use all of them
+    /// @return None with less than two species or if `Individual::distance` is not available
+    pub fn silhouette_score(&self, samples: Option<usize>) -> Option<F> {
+        self.species_collection.silhouette(samples, &mut *self.rng.borrow_mut())
+    }
+
+    /// 2D embedding of the population (classical MDS over the pairwise distances) labeled with the species,
+    /// to plot the speciation. See `analysis::write_embedding_csv` and `analysis::write_embedding_json`.
+    ///
+    /// @param samples number of individuals (randomly chosen) to embed, None to use all of them.
+    /// The cost is quadratic in the number of individuals.
+    /// @return None if `Individual::distance` is not available
+    pub fn population_embedding(&self, samples: Option<usize>) -> Option<Vec<EmbeddingPoint>> {
+        let mut individuals: Vec<(usize, &I)> = self.species_collection.iter()
+            .flat_map(|species| species.iter().map(move |individual| (species.id, individual)))
+            .collect();
+        if let Some(samples) = samples {
+            if samples < individuals.len() {
+                individuals = individuals.choose_multiple(&mut *self.rng.borrow_mut(), samples).cloned().collect();
+            }
+        }
+
+        let distances = pairwise_distances(individuals.iter().map(|(_, individual)| *individual))?;
+        let points = classical_mds(&distances).into_iter()
+            .zip(individuals)
+            .map(|((x, y), (species_id, _))| EmbeddingPoint { species_id, x, y })
+            .collect();
+        Some(points)
+    }
+
+    /// Iterates through the species, for example to read their statistics
+    pub fn iter_species(&self) -> std::slice::Iter<'_, Species<I, F>> {
+        self.species_collection.iter()
+    }
+
+    /// Iterates through the individuals of all species
+    pub fn iter_individuals(&self) -> impl Iterator<Item=&I> + '_ {
+        self.species_collection.iter().flat_map(|species| species.iter())
+    }
+
+    /// Iterates through the (mutable) individuals of all species, e.g. to post-process them.
+    /// Changes to the genome do not move an individual to another species.
+    pub fn iter_individuals_mut(&mut self) -> impl Iterator<Item=&mut I> + '_ {
+        self.species_collection.individuals_mut()
     }
 
     pub fn species_count(&self) -> usize {
@@ -59,27 +362,145 @@ where
         self.species_collection.count_individuals()
     }
 
+    /// Excludes the species from reproduction without removing it.
+    /// The individuals of a locked species are carried over unchanged to the next generations
+    /// and its offspring allocation is redistributed among the other species.
+    /// The lock is dropped by `next_generation` when the species does not exist anymore.
+    ///
+    /// Returns false if the species was already locked.
+    pub fn lock_species(&mut self, species_id: usize) -> bool {
+        self.locked_species.insert(species_id)
+    }
+
+    /// Allows the species to reproduce again.
+    ///
+    /// Returns false if the species was not locked.
+    pub fn unlock_species(&mut self, species_id: usize) -> bool {
+        self.locked_species.remove(&species_id)
+    }
+
+    /// Allows all species to reproduce again
+    pub fn unlock_all_species(&mut self) {
+        self.locked_species.clear()
+    }
+
+    pub fn is_species_locked(&self, species_id: usize) -> bool {
+        self.locked_species.contains(&species_id)
+    }
+
+    /// Ids of the species currently excluded from reproduction
+    pub fn locked_species(&self) -> &HashSet<usize> {
+        &self.locked_species
+    }
+
+    /// Number of individuals inside the locked species
+    fn count_locked_individuals(&self) -> usize {
+        self.species_collection.iter()
+            .filter(|species| self.locked_species.contains(&species.id))
+            .map(|species| species.len())
+            .sum()
+    }
+
     /// Creates the species. It takes a list of individuals and splits them into multiple species,
     /// grouping the compatible individuals together.
     ///
     /// *WARNING! THIS FUNCTION TAKES OWNERSHIP OF THE SOURCE ITERATOR FOR INDIVIDUALS*
-    pub fn speciate<It: Iterator<Item=I>>(&mut self, source_population: It) {
+    pub fn speciate<It: Iterator<Item=I>>(&mut self, conf: &Conf, source_population: It) {
         // Clear out the species list
         self.species_collection.clear();
+        self.speciation_fallback = false;
+
+        match conf.speciation_strategy {
+            SpeciationStrategy::FirstMatch | SpeciationStrategy::BestMatch => self.assign_to_species(conf, source_population),
+            SpeciationStrategy::KMedoids { species, max_iterations } => {
+                let species = conf.max_species.map_or(species, |max_species| species.min(max_species));
+                self.cluster_into_species(conf, source_population.collect(), species, max_iterations);
+            }
+        }
+
+        if conf.merge_compatible_species {
+            self.species_collection.merge_compatible(conf, &self.locked_species);
+        }
+    }
+
+    /// Re-creates the species keeping the current species as anchors.
+    /// The individuals of the current species are discarded, while their id, age and representative are kept:
+    /// the new individuals are assigned to the species with a compatible representative and new species
+    /// are created only when nothing matches. Species that receive no individuals are removed.
+    ///
+    /// *WARNING! THIS FUNCTION TAKES OWNERSHIP OF THE SOURCE ITERATOR FOR INDIVIDUALS*
+    pub fn respeciate<It: Iterator<Item=I>>(&mut self, conf: &Conf, source_population: It) {
+        for species in self.species_collection.iter_mut() {
+            species.set_individuals(std::iter::empty());
+        }
+
+        self.assign_to_species(conf, source_population);
+
+        self.species_collection.cleanup();
+
+        if conf.merge_compatible_species {
+            self.species_collection.merge_compatible(conf, &self.locked_species);
+        }
+    }
+
+    /// Inserts every individual in a compatible species (see `SpeciesCollection::find_compatible`)
+    /// that is not full (see `Conf::species_capacity`), creating a new species if none is found.
+    fn assign_to_species<It: Iterator<Item=I>>(&mut self, conf: &Conf, source_population: It) {
+        let individuals: Vec<I> = source_population.collect();
+        let capacity: Option<usize> = conf.species_capacity
+            .map(|fraction| ((fraction * individuals.len() as f64).ceil() as usize).max(1));
+        // Ids of the species that reached their capacity
+        let mut full_species: HashSet<usize> = HashSet::new();
 
         // NOTE: we are comparing the new generation's genomes to the representative from the previous generation!
         // Any new species that is created is assigned a representative from the new generation.
-        'individuals: for individual in source_population {
-            // Iterate through
-            for species in self.species_collection.iter_mut() {
-                if species.is_compatible(&individual) {
-                    species.insert(individual);
-                    continue 'individuals;
+        for individual in individuals {
+            if let Some(compatible) = self.species_collection.find_compatible(&individual, conf, &full_species) {
+                let species = self.species_collection.get_mut(compatible);
+                species.insert(individual);
+                if capacity.is_some_and(|capacity| species.len() >= capacity) {
+                    full_species.insert(species.id);
                 }
+                continue;
+            }
+            // No compatible species was found, create a new one (or join the nearest if there are too many)
+            if conf.max_species.is_some_and(|max_species| self.species_collection.len() >= max_species) {
+                if let Some(nearest) = self.species_collection.nearest(&individual, &HashSet::new()) {
+                    self.species_collection.get_mut(nearest).insert(individual);
+                    continue;
+                }
+            }
+            let species_id = self.species_collection.allocate_species_id(conf.species_id_policy, &mut self.next_species_id, &self.locked_species);
+            self.species_collection.push(Species::new(individual, species_id));
+            if capacity.is_some_and(|capacity| capacity <= 1) {
+                full_species.insert(species_id);
+            }
+        }
+    }
+
+    /// Partitions the individuals in `species` new species with `k_medoids`, the medoids become the
+    /// representatives. Falls back to `assign_to_species` if the distance is not available (see `speciation_fallback`).
+    fn cluster_into_species(&mut self, conf: &Conf, individuals: Vec<I>, species: usize, max_iterations: usize) {
+        let distances = match pairwise_distances(individuals.iter()) {
+            Some(distances) => distances,
+            None => {
+                self.speciation_fallback = true;
+                self.assign_to_species(conf, individuals.into_iter());
+                return;
+            }
+        };
+        let (medoids, clusters) = k_medoids(&distances, species, max_iterations);
+
+        let mut individuals: Vec<Option<I>> = individuals.into_iter().map(Some).collect();
+        let first_cluster = self.species_collection.len();
+        for &medoid in &medoids {
+            let species_id = self.species_collection.allocate_species_id(conf.species_id_policy, &mut self.next_species_id, &self.locked_species);
+            self.species_collection.push(Species::new(individuals[medoid].take().unwrap(), species_id));
+        }
+        for (individual, cluster) in individuals.into_iter().zip(clusters) {
+            if let Some(individual) = individual {
+                self.species_collection.get_mut(first_cluster + cluster).insert(individual);
             }
-            // No compatible species was found, create a new one
-            self.species_collection.push(Species::new(individual, self.next_species_id));
-            self.next_species_id += 1;
         }
     }
 
@@ -88,33 +509,126 @@ where
     {
         for species in self.species_collection.iter_mut() {
             for individual in species.iter_mut() {
-                let fit: Option<F> = individual.fitness();
-                if fit.is_none() {
-                    let fitness: F = evaluate_individual(individual);
-                    let individual_fitness: Option<F> = individual.fitness();
-                    assert!(individual_fitness.is_some());
-                    assert_eq!(fitness, individual_fitness.unwrap());
+                if individual.fitness().is_none() {
+                    population::evaluate_individual(individual, &mut evaluate_individual);
                 }
             }
         }
     }
 
+    /// Updates age and stagnation of the species and computes the adjusted fitness of the individuals.
+    ///
+    /// Panics when `try_update` fails.
     pub fn update(&mut self, conf: &Conf) -> &mut Self {
+        match self.try_update(conf) {
+            Ok(genus) => genus,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Same as `update`, returns an error instead of panicking on negative fitness with
+    /// `NegativeFitnessPolicy::Reject`, on `FitnessSharing::Kernel` without `Individual::distance` or on an
+    /// invalid configuration (see `Conf::validate`). The genus is not modified when an error is returned.
+    pub fn try_update(&mut self, conf: &Conf) -> Result<&mut Self, String> {
+        conf.validate()?;
+        if let FitnessSharing::Kernel { .. } = conf.fitness_sharing {
+            if self.iter_individuals().next().is_some_and(|individual| individual.distance(individual).is_none()) {
+                return Err("FitnessSharing::Kernel requires Individual::distance".to_string());
+            }
+        }
+        if conf.negative_fitness_policy == NegativeFitnessPolicy::Reject && conf.adjusted_fitness_mode == AdjustedFitnessMode::Raw {
+            if let Some(min_fitness) = self.species_collection.min_fitness().filter(|fitness| *fitness < F::zero()) {
+                return Err(format!("Negative fitness {:?} is not allowed by NegativeFitnessPolicy::Reject", min_fitness));
+            }
+        }
+
+        self.update_global_stagnation();
+        if let Some(mass_extinction) = conf.mass_extinction {
+            if self.generations_without_improvement >= mass_extinction.stagnation {
+                self.mass_extinction(mass_extinction.surviving_species);
+            }
+        }
+        self.hypermutation_factor = match conf.hypermutation {
+            None => 1.0,
+            Some(hypermutation) if self.generations_without_improvement >= hypermutation.stagnation => hypermutation.factor,
+            Some(hypermutation) => (self.hypermutation_factor * hypermutation.annealing_rate).max(1.0),
+        };
+        if let StagnationPolicy::Remove { protected } = conf.stagnation_policy {
+            self.species_collection.remove_stagnant(conf.species_max_stagnation, protected, &self.locked_species);
+        }
         // Update species stagbnation and stuff
         self.species_collection.compute_update();
+        for species in self.species_collection.iter_mut() {
+            species.record_best_fitness(conf.fitness_history_length);
+        }
         // Update adjusted fitnesses
-        self.species_collection.compute_adjust_fitness(conf);
-        self
+        self.skipped_species = self.species_collection.compute_adjust_fitness(conf, self.generation);
+        Ok(self)
     }
 
 
+    /// Updates the best fitness of the genus and the number of generations without improvement
+    fn update_global_stagnation(&mut self) {
+        let best_fitness = self.species_collection.iter()
+            .filter_map(|species| species.get_best_fitness())
+            .fold(None, |best: Option<F>, fitness| Some(best.map_or(fitness, |best| best.max(fitness))));
+        match (best_fitness, self.best_fitness) {
+            (Some(best_fitness), Some(old_best_fitness)) if best_fitness <= old_best_fitness => {
+                self.generations_without_improvement += 1;
+            }
+            (Some(best_fitness), _) => {
+                self.best_fitness = Some(best_fitness);
+                self.generations_without_improvement = 0;
+            }
+            (None, _) => self.generations_without_improvement += 1,
+        }
+    }
+
+    /// Removes all the species except the `surviving_species` best ones (and the locked ones),
+    /// the surviving species keep only their best individual. The next `generate_new_individuals`
+    /// refills the population from the survivors.
+    fn mass_extinction(&mut self, surviving_species: usize) {
+        let mut survivors = self.locked_species.clone();
+        for _ in 0..surviving_species.max(1) {
+            match self.species_collection.get_best_excluding(&survivors) {
+                Some(species_i) => survivors.insert(self.species_collection.get(species_i).id),
+                None => break,
+            };
+        }
+
+        for species in self.species_collection.iter_mut() {
+            if self.locked_species.contains(&species.id) {
+                continue;
+            }
+            let amount = if survivors.contains(&species.id) { 1 } else { 0 };
+            species.retain_best(amount);
+        }
+        self.species_collection.cleanup();
+        self.generations_without_improvement = 0;
+        self.last_mass_extinction = Some(self.generation);
+    }
+
+    /// Same as `generate_new_individuals`, with all the operators provided by one implementor
+    pub fn generate_new_individuals_with<O: ReproductionOperators<I, F>>(&mut self, conf: &Conf, operators: &mut O) -> GenusSeed<I, F> {
+        // the operators are never called re-entrantly, one mutable borrow at a time is enough
+        let operators = RefCell::new(operators);
+        self.generate_new_individuals(
+            conf,
+            &mut |it| operators.borrow_mut().select(it),
+            &mut |it| operators.borrow_mut().select_parents(it),
+            &mut |parent: &I| operators.borrow_mut().reproduce(parent),
+            &mut |parent1: &I, parent2: &I| operators.borrow_mut().crossover(parent1, parent2),
+            &mut |individual: &mut I| operators.borrow_mut().mutate(individual),
+        )
+    }
+
     /// Creates the genus for the next generation.
     /// The species are copied over so that `this` Genus is not invalidated.
     ///
     /// @param conf Species configuration object
-    /// @param selection function to select 1 parent (can be called even if crossover is enabled, when there is not more
-    /// than one parent possible)
-    /// @param parent_selection function to select 2 parents (only possibly called if crossover is enabled)
+    /// @param selection function to select 1 parent (can be called even if crossover is chosen, when there is not more
+    /// than one parent possible, and selects each of the two parents of an interspecies crossover)
+    /// @param parent_selection function to select 2 parents (only possibly called if crossover is chosen, see `Conf::crossover_probability`)
     /// @param reproduce_individual_1 function to crossover and create new individuals from 1 parent
     /// @param crossover_individual_2 function to crossover and create new individuals from 2 parents
     /// @param mutate_individual function that mutates an individual
@@ -122,8 +636,8 @@ where
     /// size of the new population is passed in as a parameter. The size can vary a lot from one generation to the next.
     /// @param evaluate_individual function to evaluate new individuals
     /// @return the genus of the next generation
-    pub fn generate_new_individuals<'a, 'individual, SelectionF, ParentSelectionF, ReproduceI1F, CrossoverI2F, MutateF>(
-        &'a mut self,
+    pub fn generate_new_individuals<SelectionF, ParentSelectionF, ReproduceI1F, CrossoverI2F, MutateF>(
+        &mut self,
         conf: &Conf,
         selection: &mut SelectionF,
         parent_selection: &mut ParentSelectionF,
@@ -132,146 +646,477 @@ where
         mutate_individual: &mut MutateF,
     ) -> GenusSeed<I, F>
         where
-            I: 'individual,
-            SelectionF: FnMut(Box<SpeciesIter<I, F>>) -> &'individual I,
-            ParentSelectionF: FnMut(Box<SpeciesIter<I, F>>) -> (&'individual I,&'individual I),
+            SelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> &'s I,
+            ParentSelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> (&'s I, &'s I),
             ReproduceI1F: FnMut(&I) -> I,
             CrossoverI2F: FnMut(&I, &I) -> I,
             MutateF: FnMut(&mut I),
     {
-        // Calculate offspring amount
-        let offspring_amounts: Vec<usize> = self.count_offsprings(conf.total_population_size)
+        self.generate_new_individuals_batch(
+            conf,
+            selection,
+            parent_selection,
+            &mut |parent: &I| vec![reproduce_individual_1(parent)],
+            &mut |parent1: &I, parent2: &I| vec![crossover_individual_2(parent1, parent2)],
+            mutate_individual,
+        )
+    }
+
+    /// Same as `generate_new_individuals`, for reproduction and crossover functions that can fail: a failing
+    /// function is called again with the same parents up to `conf.operator_retries` times, then the offspring
+    /// is a clone of the (first) parent. The failures are counted in the returned `GenusSeed`.
+    pub fn generate_new_individuals_fallible<SelectionF, ParentSelectionF, ReproduceI1F, CrossoverI2F, MutateF>(
+        &mut self,
+        conf: &Conf,
+        selection: &mut SelectionF,
+        parent_selection: &mut ParentSelectionF,
+        reproduce_individual_1: &mut ReproduceI1F,
+        crossover_individual_2: &mut CrossoverI2F,
+        mutate_individual: &mut MutateF,
+    ) -> GenusSeed<I, F>
+        where
+            SelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> &'s I,
+            ParentSelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> (&'s I, &'s I),
+            ReproduceI1F: FnMut(&I) -> Result<I, OperatorError>,
+            CrossoverI2F: FnMut(&I, &I) -> Result<I, OperatorError>,
+            MutateF: FnMut(&mut I),
+    {
+        let failures: Cell<usize> = Cell::new(0);
+        let fallbacks: Cell<usize> = Cell::new(0);
+        let retry = |parent: &I, operator: &mut dyn FnMut() -> Result<I, OperatorError>| -> I {
+            for _ in 0..=conf.operator_retries {
+                match operator() {
+                    Ok(child) => return child,
+                    Err(_) => failures.set(failures.get() + 1),
+                }
+            }
+            fallbacks.set(fallbacks.get() + 1);
+            parent.clone()
+        };
+        let mut seed = self.generate_new_individuals_batch(
+            conf,
+            selection,
+            parent_selection,
+            &mut |parent: &I| vec![retry(parent, &mut || reproduce_individual_1(parent))],
+            &mut |parent1: &I, parent2: &I| vec![retry(parent1, &mut || crossover_individual_2(parent1, parent2))],
+            mutate_individual,
+        );
+        seed.operator_failures = failures.get();
+        seed.operator_fallbacks = fallbacks.get();
+        seed
+    }
+
+    /// Same as `generate_new_individuals`, with reproduction functions creating several offspring at once
+    /// (e.g. the two children of a crossover) from the same parents, all of them mutated.
+    ///
+    /// The offspring of a batch are given to the species of the parents in order, the ones exceeding the
+    /// offspring amount of the species are discarded. A batch must not be empty.
+    pub fn generate_new_individuals_batch<SelectionF, ParentSelectionF, ReproduceI1F, CrossoverI2F, MutateF>(
+        &mut self,
+        conf: &Conf,
+        selection: &mut SelectionF,
+        parent_selection: &mut ParentSelectionF,
+        reproduce_individual_1: &mut ReproduceI1F,
+        crossover_individual_2: &mut CrossoverI2F,
+        mutate_individual: &mut MutateF,
+    ) -> GenusSeed<I, F>
+        where
+            SelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> &'s I,
+            ParentSelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> (&'s I, &'s I),
+            ReproduceI1F: FnMut(&I) -> Vec<I>,
+            CrossoverI2F: FnMut(&I, &I) -> Vec<I>,
+            MutateF: FnMut(&mut I),
+    {
+        self.generate_new_individuals_core::<_, _, BatchSelectionFn<I, F>, _, _, _>(
+            conf,
+            ParentSelection::Single(selection, parent_selection),
+            reproduce_individual_1,
+            crossover_individual_2,
+            mutate_individual,
+        )
+    }
+
+    /// Same as `generate_new_individuals`, with the parents of the offspring of every species chosen all at
+    /// once (e.g. with `StochasticUniversalSampling::select_batch`) instead of one offspring at a time.
+    ///
+    /// `batch_selection` receives the individuals of a species eligible as parents and the number of parents
+    /// to choose: the first call for a species asks for enough parents for all its offspring (two each when
+    /// crossover is possible), it is called again only if more are needed (e.g. after rejected offspring).
+    /// The parents are used in the order of the batch, the second parent of an interspecies crossover is a
+    /// batch of one from the mate species.
+    pub fn generate_new_individuals_with_batch_selection<BatchSelectionF, ReproduceI1F, CrossoverI2F, MutateF>(
+        &mut self,
+        conf: &Conf,
+        batch_selection: &mut BatchSelectionF,
+        reproduce_individual_1: &mut ReproduceI1F,
+        crossover_individual_2: &mut CrossoverI2F,
+        mutate_individual: &mut MutateF,
+    ) -> GenusSeed<I, F>
+        where
+            BatchSelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>, usize) -> Vec<&'s I>,
+            ReproduceI1F: FnMut(&I) -> I,
+            CrossoverI2F: FnMut(&I, &I) -> I,
+            MutateF: FnMut(&mut I),
+    {
+        self.generate_new_individuals_core::<SelectionFn<I, F>, ParentSelectionFn<I, F>, _, _, _, _>(
+            conf,
+            ParentSelection::Batch(batch_selection),
+            &mut |parent: &I| vec![reproduce_individual_1(parent)],
+            &mut |parent1: &I, parent2: &I| vec![crossover_individual_2(parent1, parent2)],
+            mutate_individual,
+        )
+    }
+
+    /// Generation of the offspring shared by all the `generate_new_individuals` variants
+    fn generate_new_individuals_core<SelectionF, ParentSelectionF, BatchSelectionF, ReproduceI1F, CrossoverI2F, MutateF>(
+        &mut self,
+        conf: &Conf,
+        mut parent_selection: ParentSelection<SelectionF, ParentSelectionF, BatchSelectionF>,
+        reproduce_individual_1: &mut ReproduceI1F,
+        crossover_individual_2: &mut CrossoverI2F,
+        mutate_individual: &mut MutateF,
+    ) -> GenusSeed<I, F>
+        where
+            SelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> &'s I,
+            ParentSelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> (&'s I, &'s I),
+            BatchSelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>, usize) -> Vec<&'s I>,
+            ReproduceI1F: FnMut(&I) -> Vec<I>,
+            CrossoverI2F: FnMut(&I, &I) -> Vec<I>,
+            MutateF: FnMut(&mut I),
+    {
+        if let Err(error) = conf.validate() {
+            panic!("{}", error);
+        }
+
+        // Calculate offspring amount, the locked species keep their slots
+        let locked_individuals = self.count_locked_individuals();
+        assert!(locked_individuals < conf.total_population_size,
+                "locked species occupy the entire population, nothing can reproduce");
+        let (offspring_amounts, elite_slots) = self.count_offsprings(conf, conf.total_population_size - locked_individuals)
             .expect("count offspring to be successful");
 
         // Clone Species
         let mut new_species_collection: Vec<RcSpecies<I,F>> = Vec::new();
         let mut orphans: Vec<Rc<RefCell<I>>> = Vec::new();
+        let mut orphan_species_ids: Vec<usize> = Vec::new();
 
         // Pointers to values in new_species_collection and orphans
         let mut need_evaluation: Vec<Rc<RefCell<I>>> = Vec::new();
 
+        let mut crossover_guard_triggers: usize = 0;
+
+        // Genome hashes of the current population and of the offspring, to reject the duplicates
+        let mut known_genomes: HashSet<u64> = HashSet::new();
+        if conf.duplicate_offspring_retries > 0 {
+            known_genomes.extend(self.iter_individuals().filter_map(|individual| individual.genome_hash()));
+        }
+        let mut duplicate_offspring_rejections: usize = 0;
+        let mut orphan_regenerations: usize = 0;
+
+        let species_trial_budgets: Vec<usize> = self.species_trial_budgets(conf);
+        let mut trial_budgets: Vec<usize> = Vec::new();
+        let mut rng = self.fork_rng();
+
+        // Only the best individuals of every species can become parents
+        if conf.survival_threshold < 1.0 {
+            for species in self.species_collection.iter_mut() {
+                species.sort_by_fitness();
+            }
+        }
+
         //////////////////////////////////////////////
         // GENERATE NEW INDIVIDUALS
         for (species_i, species) in self.species_collection.iter().enumerate() {
 
             let mut new_individuals: Vec<Rc<RefCell<I>>> = Vec::new();
+            let eligible_parents = Self::eligible_parents(conf, species);
+            // offspring generated and not used yet, in reverse order
+            let mut batch: Vec<I> = Vec::new();
+            // parents chosen in advance by a batch selection and not used yet, in reverse order
+            let mut parent_pool: Vec<&I> = Vec::new();
+            let parents_per_offspring: usize = if conf.crossover_probability > 0.0 { 2 } else { 1 };
 
-            for n_offspring in 0_usize..offspring_amounts[species_i] {
-                for _ in 0..n_offspring {
-                    let new_individual: Rc<RefCell<I>> = Rc::new(RefCell::new(
-                        Self::generate_new_individual::<
-                            SpeciesIter<'a, I, F>,
-                            SelectionF,
-                            ParentSelectionF,
-                            ReproduceI1F,
-                            CrossoverI2F,
-                            MutateF>
-                        (
+            for offspring_i in 0..offspring_amounts[species_i] {
+                let mut duplicate_attempts: usize = 0;
+                let mut orphan_attempts: usize = 0;
+                let (new_individual, compatible) = loop {
+                    if batch.is_empty() {
+                        let crossover = rng.gen_bool(conf.crossover_probability);
+                        let mate_species = if crossover { self.choose_mate_species(conf, species_i, &mut rng) } else { None };
+                        let (parent1, parent2) = Self::choose_parents(
+                            &mut parent_selection,
+                            species.iter_first(eligible_parents),
+                            mate_species.map(|mate_species| mate_species.iter_first(Self::eligible_parents(conf, mate_species))),
+                            crossover,
+                            &mut parent_pool,
+                            (offspring_amounts[species_i] - offspring_i) * parents_per_offspring,
+                        );
+                        batch = Self::generate_offspring(
                             conf,
-                            species.iter(),
-                            selection,
-                            parent_selection,
+                            parent1,
+                            parent2,
+                            mate_species.is_some(),
                             reproduce_individual_1,
                             crossover_individual_2,
                             mutate_individual,
-                        )));
-
-                    // if the new individual is compatible with the species, otherwise create new.
-                    need_evaluation.push(new_individual.clone());
-                    if species.is_compatible(&new_individual.borrow()) {
-                        new_individuals.push(new_individual);
-                    } else {
-                        orphans.push(new_individual);
+                            &mut crossover_guard_triggers,
+                        );
+                        assert!(!batch.is_empty(), "the reproduction functions must create at least one offspring");
+                        batch.reverse();
+                    }
+                    let new_individual = batch.pop().unwrap();
+                    if duplicate_attempts < conf.duplicate_offspring_retries
+                        && new_individual.genome_hash().is_some_and(|hash| known_genomes.contains(&hash)) {
+                        duplicate_attempts += 1;
+                        duplicate_offspring_rejections += 1;
+                        continue;
+                    }
+                    let compatible = species.is_compatible(&new_individual, conf);
+                    if let OrphanPolicy::Regenerate { attempts } = conf.orphan_policy {
+                        if !compatible && orphan_attempts < attempts {
+                            orphan_attempts += 1;
+                            orphan_regenerations += 1;
+                            continue;
+                        }
                     }
+                    break (new_individual, compatible);
+                };
+                if conf.duplicate_offspring_retries > 0 {
+                    known_genomes.extend(new_individual.genome_hash());
+                }
+                let new_individual: Rc<RefCell<I>> = Rc::new(RefCell::new(new_individual));
+
+                // if the new individual is compatible with the species, otherwise create new.
+                need_evaluation.push(new_individual.clone());
+                trial_budgets.push(species_trial_budgets[species_i]);
+                if compatible {
+                    new_individuals.push(new_individual);
+                } else {
+                    orphans.push(new_individual);
+                    orphan_species_ids.push(species.id);
                 }
             }
 
             new_species_collection.push(
-                species.clone_with_new_individuals(new_individuals.into_iter())
+                species.clone_with_new_individuals(new_individuals.into_iter(), conf, &mut rng)
             );
         };
 
-        // Pointers to current const species_collection
-        let old_species_individuals_vec = {
-            self.species_collection.iter_mut()
-                .map(|species| species.drain_individuals().collect()).collect()
-        };
-
-        GenusSeed::new(
+        let mut seed = GenusSeed::new(
             orphans,
             new_species_collection,
             need_evaluation,
-            old_species_individuals_vec)
+            conf.total_population_size);
+        seed.crossover_guard_triggers = crossover_guard_triggers;
+        seed.skipped_species = self.skipped_species.len();
+        seed.duplicate_offspring_rejections = duplicate_offspring_rejections;
+        seed.orphan_regenerations = orphan_regenerations;
+        seed.orphan_species_ids = orphan_species_ids;
+        seed.trial_budgets = trial_budgets;
+        seed.elite_slots = elite_slots;
+        seed
     }
 
-    /// Generate a new individual from randomly selected parents + mutation
+    /// Number of individuals of the species eligible as parents, see `Conf::survival_threshold`
+    fn eligible_parents(conf: &Conf, species: &Species<I, F>) -> usize {
+        let eligible = (species.len() as f64 * conf.survival_threshold).ceil() as usize;
+        eligible.clamp(1, species.len())
+    }
+
+    /// With probability `conf.interspecies_mating_rate` chooses (uniformly) another unlocked species to draw
+    /// the second parent of the next crossover from.
+    ///
+    /// @param species_i index of the species of the first parent
+    /// @return the mate species, None for a crossover inside the species
+    fn choose_mate_species<R: Rng>(&self, conf: &Conf, species_i: usize, rng: &mut R) -> Option<&Species<I, F>> {
+        // the locked species do not pass their genes on
+        let candidates: Vec<&Species<I, F>> = self.species_collection.iter()
+            .enumerate()
+            .filter(|(i, species)| *i != species_i && !self.locked_species.contains(&species.id))
+            .map(|(_, species)| species)
+            .collect();
+        if candidates.is_empty() || !rng.gen_bool(conf.interspecies_mating_rate) {
+            return None;
+        }
+        candidates.choose(rng).copied()
+    }
+
+    /// Chooses the parents of the next offspring
+    ///
+    /// @param parent_selection how to choose the parents
+    /// @param population the parents eligible in the species
+    /// @param mate_population parents eligible in another species to take the second parent from, for an interspecies crossover
+    /// @param crossover if to create the individual by crossover (see `Conf::crossover_probability`)
+    /// @param parent_pool parents of the species chosen in advance by a batch selection, in reverse order
+    /// @param pool_refill number of parents a batch selection chooses when the pool runs out
+    /// @return the parent, with the second parent for a crossover
+    fn choose_parents<'g, SelectionF, ParentSelectionF, BatchSelectionF>(
+        parent_selection: &mut ParentSelection<SelectionF, ParentSelectionF, BatchSelectionF>,
+        population: SpeciesIter<'g, I, F>,
+        mate_population: Option<SpeciesIter<'g, I, F>>,
+        crossover: bool,
+        parent_pool: &mut Vec<&'g I>,
+        pool_refill: usize,
+    ) -> (&'g I, Option<&'g I>)
+    where
+        SelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> &'s I,
+        ParentSelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>) -> (&'s I, &'s I),
+        BatchSelectionF: for<'s> FnMut(Box<SpeciesIter<'s, I, F>>, usize) -> Vec<&'s I>,
+    {
+        let parent_pool_size: usize = population.len();
+        assert!(parent_pool_size > 0);
+        let two_parents = crossover && (parent_pool_size > 1 || mate_population.is_some());
+
+        match parent_selection {
+            ParentSelection::Single(selection, parent_selection) => {
+                if !two_parents {
+                    return (selection(Box::new(population)), None);
+                }
+                match mate_population {
+                    Some(mate_population) => (selection(Box::new(population)), Some(selection(Box::new(mate_population)))),
+                    None => {
+                        let (parent1, parent2) = parent_selection(Box::new(population));
+                        (parent1, Some(parent2))
+                    }
+                }
+            }
+            ParentSelection::Batch(batch_selection) => {
+                let needed = if two_parents && mate_population.is_none() { 2 } else { 1 };
+                if parent_pool.len() < needed {
+                    let amount = pool_refill.max(needed);
+                    let mut refill = batch_selection(Box::new(population), amount);
+                    assert_eq!(refill.len(), amount, "the batch selection must choose the requested number of parents");
+                    refill.reverse();
+                    refill.append(parent_pool);
+                    *parent_pool = refill;
+                }
+                let parent1 = parent_pool.pop().unwrap();
+                if !two_parents {
+                    return (parent1, None);
+                }
+                let parent2 = match mate_population {
+                    Some(mate_population) => *batch_selection(Box::new(mate_population), 1).first()
+                        .expect("the batch selection must choose the requested number of parents"),
+                    None => parent_pool.pop().unwrap(),
+                };
+                (parent1, Some(parent2))
+            }
+        }
+    }
+
+    /// Generate new individuals from the chosen parents + mutation
     ///
     /// @param conf Species configuration object
-    /// @param population_begin start of the species population
-    /// @param pop_end end of the species population
-    /// @param selection function to select 1 parent (can be called even if crossover is enabled, when there is not more
-    /// than one parent possible)
-    /// @param parent_selection function to select 2 parents (only possibly called if crossover is enabled)
+    /// @param parent1 the (first) parent
+    /// @param parent2 the second parent, to create the individuals by crossover
+    /// @param interspecies if the second parent comes from another species
     /// @param reproduce_1 function to crossover and create new individuals from 1 parent
     /// @param reproduce_2 function to crossover and create new individuals from 2 parents
     /// @param mutate function that mutates an individual
-    /// @return the genus of the next generation
-    fn generate_new_individual<'a, 'individual, It, SelectionF, ParentSelectionF, ReproduceI1F, CrossoverI2F, MutateF>(
+    /// @param crossover_guard_triggers counter increased every time the crossover compatibility guard
+    /// falls back to asexual reproduction
+    /// @return the new individuals, all from the same parents
+    #[allow(clippy::too_many_arguments)]
+    fn generate_offspring<ReproduceI1F, CrossoverI2F, MutateF>(
         conf: &Conf,
-        population: It,
-        selection: &mut SelectionF,
-        parent_selection: &mut ParentSelectionF,
+        parent1: &I,
+        parent2: Option<&I>,
+        interspecies: bool,
         reproduce_individual_1: &mut ReproduceI1F,
         crossover_individual_2: &mut CrossoverI2F,
         mutate_individual: &mut MutateF,
-    ) -> I
+        crossover_guard_triggers: &mut usize,
+    ) -> Vec<I>
     where
-        I: 'individual,
-        It: ExactSizeIterator<Item=&'a I> + Sized,
-        SelectionF: FnMut(Box<It>) -> &'individual I,
-        ParentSelectionF: FnMut(Box<It>) -> (&'individual I,&'individual I),
-        ReproduceI1F: FnMut(&I) -> I,
-        CrossoverI2F: FnMut(&I, &I) -> I,
+        ReproduceI1F: FnMut(&I) -> Vec<I>,
+        CrossoverI2F: FnMut(&I, &I) -> Vec<I>,
         MutateF: FnMut(&mut I),
     {
-        let parent_pool_size: usize = population.len();
-        assert!(parent_pool_size > 0);
+        let mut children: Vec<I> = match parent2 {
+            // Crossover between incompatible parents mostly produces broken children,
+            // fall back to asexual reproduction from the first parent.
+            // Interspecies parents are incompatible by definition, they are not guarded.
+            Some(parent2) if conf.crossover_compatibility_guard && !interspecies && !are_crossover_compatible(parent1, parent2, conf) => {
+                *crossover_guard_triggers += 1;
+                reproduce_individual_1(parent1)
+            }
+            Some(parent2) => crossover_individual_2(parent1, parent2),
+            None => reproduce_individual_1(parent1),
+        };
 
-        // Crossover
-        let mut child: I =
-            if conf.crossover && parent_pool_size > 1 {
-                let parents = parent_selection(Box::new(population));
-                let parent1 = parents.0;
-                let parent2 = parents.1;
-                crossover_individual_2(parent1, parent2)
-            } else {
-                let parent = selection(Box::new(population));
-                reproduce_individual_1(parent)
-            };
+        for child in children.iter_mut() {
+            mutate_individual(child);
+        }
+        children
+    }
 
-        mutate_individual(&mut child);
-        child
+    /// Calculates the number of evaluation trials for the offspring of each species, see `TrialBudget`.
+    ///
+    /// @return a vector with the number of trials, the index corresponds to the index of the species
+    fn species_trial_budgets(&self, conf: &Conf) -> Vec<usize> {
+        let species_n = self.species_collection.len();
+        match conf.trial_budget {
+            TrialBudget::Fixed(trials) => vec![trials; species_n],
+            TrialBudget::SpeciesRank { min, max } => {
+                assert!(min <= max, "TrialBudget min must not be greater than max");
+                // rank 0 is the best species
+                let mut order: Vec<usize> = (0..species_n).collect();
+                order.sort_by(|&a, &b| {
+                    let fitness_a = self.species_collection.get(a).get_best_fitness();
+                    let fitness_b = self.species_collection.get(b).get_best_fitness();
+                    fitness_b.partial_cmp(&fitness_a).unwrap_or(std::cmp::Ordering::Equal)
+                });
+                let mut budgets = vec![max; species_n];
+                if species_n > 1 {
+                    for (rank, species_i) in order.into_iter().enumerate() {
+                        budgets[species_i] = max - (max - min) * rank / (species_n - 1);
+                    }
+                }
+                budgets
+            }
+            TrialBudget::SpeciesStagnation { min, max } => {
+                assert!(min <= max, "TrialBudget min must not be greater than max");
+                self.species_collection.iter()
+                    .map(|species| {
+                        if conf.species_max_stagnation == 0 {
+                            return min;
+                        }
+                        let stagnation = species.age().no_improvements.min(conf.species_max_stagnation);
+                        max - (max - min) * stagnation / conf.species_max_stagnation
+                    })
+                    .collect()
+            }
+        }
     }
 
     /// Calculates the number of offsprings allocated for each individual.
     /// The total of allocated individuals will be `number_of_individuals`
+    /// Locked species are always allocated 0 offsprings, species that are not stagnating at least `conf.min_offspring`.
+    /// The slots reserved to the elites of every species (see `Conf::elitism` and `Conf::preserve_champion`)
+    /// are subtracted from the offspring of the species.
     ///
+    /// @param conf Species configuration object
     /// @param number_of_individuals Total number of individuals to generate
-    /// @return a vector of integers representing the number of allocated individuals for each species.
-    /// The index of this list corresponds to the same index in `this->_species_list`.
-    fn count_offsprings(&mut self, number_of_individuals: usize) -> Result<Vec<usize>, String>
+    /// @return two vectors of integers representing the number of offspring and of elite slots for each species,
+    /// they sum up to `number_of_individuals`.
+    /// The index of these lists corresponds to the same index in `this->_species_list`.
+    fn count_offsprings(&mut self, conf: &Conf, number_of_individuals: usize) -> Result<(Vec<usize>, Vec<usize>), String>
     {
-        assert!(number_of_individuals > 0);
-
-        let average_adjusted_fitness: F = self.calculate_average_fitness().expect("Couldn't calculate average fitness");
+        let mut species_offspring_amount: Vec<usize> = match self.calculate_average_fitness(number_of_individuals) {
+            Ok(average_adjusted_fitness) => self.calculate_population_size(conf, average_adjusted_fitness, number_of_individuals)?,
+            Err(error) => self.recover_fitness_collapse(conf, number_of_individuals, error)?,
+        };
 
-        let mut species_offspring_amount: Vec<usize> = self.calculate_population_size(average_adjusted_fitness);
+        let minimum_amounts = self.minimum_offspring_amounts(conf, number_of_individuals)?;
+        for (amount, minimum) in species_offspring_amount.iter_mut().zip(&minimum_amounts) {
+            *amount = (*amount).max(*minimum);
+        }
 
         let mut offspring_amount_sum: usize = species_offspring_amount.iter().sum();
         let missing_offsprings = number_of_individuals as i32 -  offspring_amount_sum as i32;
 
         if missing_offsprings != 0 {
-            self.correct_population_size(&mut species_offspring_amount, missing_offsprings);
+            self.correct_population_size(&mut species_offspring_amount, &minimum_amounts, missing_offsprings);
             offspring_amount_sum = species_offspring_amount.iter().sum();
 
             if offspring_amount_sum != number_of_individuals {
@@ -282,19 +1127,66 @@ where
             }
         }
 
-        Ok(species_offspring_amount)
+        let elite_slots = self.reserve_elite_slots(conf, &mut species_offspring_amount);
+        Ok((species_offspring_amount, elite_slots))
+    }
+
+    /// Moves the slots of the elites of every species out of `species_offspring_amount`.
+    /// A species cannot reserve more slots than its allocated amount or its number of individuals.
+    ///
+    /// @return the number of elite slots for each species
+    fn reserve_elite_slots(&self, conf: &Conf, species_offspring_amount: &mut [usize]) -> Vec<usize> {
+        // the champion is the best individual of its species
+        let champion_species_i = if conf.preserve_champion {
+            self.species_collection.get_best_excluding(&self.locked_species)
+        } else {
+            None
+        };
+        self.species_collection.iter()
+            .zip(species_offspring_amount.iter_mut())
+            .enumerate()
+            .map(|(species_i, (species, amount))| {
+                let elitism = if Some(species_i) == champion_species_i { conf.elitism.max(1) } else { conf.elitism };
+                let elites = elitism.min(*amount).min(species.len());
+                *amount -= elites;
+                elites
+            })
+            .collect()
+    }
+
+    /// Offspring quota of every species, see `Conf::min_offspring`.
+    ///
+    /// @return a vector with the minimum amount of offspring, the index corresponds to the index of the species,
+    /// an error if the quotas do not fit in `number_of_individuals`
+    fn minimum_offspring_amounts(&self, conf: &Conf, number_of_individuals: usize) -> Result<Vec<usize>, String> {
+        let minimum_amounts: Vec<usize> = self.species_collection.iter()
+            .map(|species| {
+                if self.locked_species.contains(&species.id)
+                    || species.age().no_improvements > conf.species_max_stagnation {
+                    0
+                } else {
+                    conf.min_offspring
+                }
+            })
+            .collect();
+        let quotas: usize = minimum_amounts.iter().sum();
+        if quotas > number_of_individuals {
+            return Err(format!("Minimum offspring quotas ({} in total) exceed the {} offspring to allocate",
+                               quotas, number_of_individuals));
+        }
+        Ok(minimum_amounts)
     }
 
     /// Calculates the Average fitness of the population based on the adjusted fitnesses
     ///
+    /// @param number_of_individuals Total number of individuals to generate
     /// @return the average fitness
-    fn calculate_average_fitness(&self) -> Result<F,&str> {
+    fn calculate_average_fitness(&self, number_of_individuals: usize) -> Result<F, &'static str> {
         // Calculate the total adjusted fitness
         let mut total_adjusted_fitness: F = F::zero();
-        let mut number_of_individuals: usize = 0;
-        for species in self.species_collection.iter() {
+        for species in self.species_collection.iter()
+            .filter(|species| !self.locked_species.contains(&species.id)) {
             total_adjusted_fitness = total_adjusted_fitness + species.accumulated_adjusted_fitness();
-            number_of_individuals += species.len();
         }
         if total_adjusted_fitness <= F::zero() {
             return Err("Total adjusted fitness is <= 0");
@@ -302,30 +1194,129 @@ where
 
         // Calculate the average adjusted fitness
         let average_adjusted_fitness: F = total_adjusted_fitness / F::from(number_of_individuals).unwrap();
+        if !average_adjusted_fitness.is_normal() {
+            return Err("Average adjusted fitness is not a normal number");
+        }
 
         Ok(average_adjusted_fitness)
     }
 
+    /// Allocates the offspring when the adjusted fitness collapsed, following `conf.fitness_collapse_policy`,
+    /// and counts the collapse (see `fitness_collapses`).
+    ///
+    /// @param number_of_individuals Total number of individuals to generate
+    /// @param error why the average adjusted fitness could not be calculated
+    /// @return the number of offspring of each species, it sums up to `number_of_individuals`,
+    /// an error if `conf.offspring_rounding` fails (see `calculate_population_size`)
+    fn recover_fitness_collapse(&mut self, conf: &Conf, number_of_individuals: usize, error: &str) -> Result<Vec<usize>, String> {
+        self.fitness_collapses += 1;
+        match conf.fitness_collapse_policy {
+            FitnessCollapsePolicy::Panic => panic!("Couldn't calculate average fitness: {}", error),
+            FitnessCollapsePolicy::ResetStagnation => {
+                for species in self.species_collection.iter_mut() {
+                    species.reset_no_improvements();
+                }
+                self.skipped_species = self.species_collection.compute_adjust_fitness(conf, self.generation);
+                if let Ok(average_adjusted_fitness) = self.calculate_average_fitness(number_of_individuals) {
+                    return self.calculate_population_size(conf, average_adjusted_fitness, number_of_individuals);
+                }
+            }
+            FitnessCollapsePolicy::ProportionalToSize => {}
+        }
+
+        let (sizes, capacities): (Vec<usize>, Vec<usize>) = self.species_collection.iter()
+            .map(|species| if self.locked_species.contains(&species.id) {
+                (0, 0)
+            } else {
+                (species.len(), number_of_individuals)
+            })
+            .unzip();
+        Ok(rescale_offspring_amounts(&sizes, &capacities, number_of_individuals))
+    }
+
     /// Calculates the number of offsprings allocated for each individual given the `average_adjusted_fitness`.
-    /// The function is rounding real numbers to integer numbers, so the returned vector quite possibly will not sum up
-    /// to the total population size.
+    /// The function is rounding real numbers to integer numbers following `conf.offspring_rounding`,
+    /// so the returned vector quite possibly will not sum up to the total population size.
     ///
     /// @param average_adjusted_fitness The average adjusted fitness across all the species.
+    /// @param number_of_individuals Total number of individuals to generate
     /// @return a vector of integers representing the number of allocated individuals for each species.
     /// The index of this list corresponds to the same index in `self.species_list`.
-    fn calculate_population_size(&self, average_adjusted_fitness: F) -> Vec<usize>
+    /// An error if the `OffspringRounding::Custom` strategy does not return one amount per species
+    /// summing up to `number_of_individuals`.
+    fn calculate_population_size(&self, conf: &Conf, average_adjusted_fitness: F, number_of_individuals: usize) -> Result<Vec<usize>, String>
     {
-
-        let species_offspring_amount: Vec<_> = self.species_collection.iter()
+        let shares: Vec<F> = self.species_collection.iter()
             .map(|species| {
+                if self.locked_species.contains(&species.id) {
+                    return F::zero();
+                }
                 // each species amount is given by the sum of the fitness
                 // of the individuals normalized by the average_adjusted_fitness
-                let offspring_amount: F = species.accumulated_adjusted_fitness() / average_adjusted_fitness;
-                offspring_amount.floor().to_usize().unwrap()
+                species.accumulated_adjusted_fitness() / average_adjusted_fitness
             }).collect();
 
-        return species_offspring_amount;
+        let mut amounts: Vec<usize> = shares.iter()
+            .map(|share| share.floor().to_usize().unwrap())
+            .collect();
+        let missing = number_of_individuals.saturating_sub(amounts.iter().sum());
+        let mut remainders: Vec<(usize, F)> = shares.iter()
+            .enumerate()
+            .map(|(i, share)| (i, *share - share.floor()))
+            .filter(|(_, remainder)| *remainder > F::zero())
+            .collect();
 
+        match conf.offspring_rounding {
+            // the missing offspring are added by `correct_population_size`
+            OffspringRounding::FloorBestSpecies => {}
+            OffspringRounding::LargestRemainder => add_to_largest_remainders(&mut amounts, remainders, missing),
+            OffspringRounding::StochasticRemainder => {
+                let mut rng = self.rng.borrow_mut();
+                for _ in 0..missing {
+                    let total: F = remainders.iter().map(|(_, remainder)| *remainder).sum();
+                    if total <= F::zero() {
+                        break;
+                    }
+                    let mut target = total * F::from(rng.gen::<f64>()).unwrap();
+                    let mut chosen = remainders.len() - 1;
+                    for (position, (_, remainder)) in remainders.iter().enumerate() {
+                        if target < *remainder {
+                            chosen = position;
+                            break;
+                        }
+                        target = target - *remainder;
+                    }
+                    let (i, _) = remainders.swap_remove(chosen);
+                    amounts[i] += 1;
+                }
+            }
+            OffspringRounding::RoundHalfEven => {
+                for (amount, share) in amounts.iter_mut().zip(&shares) {
+                    let remainder = *share - share.floor();
+                    let half = F::from(0.5).unwrap();
+                    if remainder > half || (remainder == half && *amount % 2 == 1) {
+                        *amount += 1;
+                    }
+                }
+            }
+            OffspringRounding::Custom(round) => {
+                let shares: Vec<f64> = shares.iter()
+                    .map(|share| share.to_f64().unwrap())
+                    .collect();
+                amounts = round(&shares, number_of_individuals);
+                if amounts.len() != shares.len() || amounts.iter().sum::<usize>() != number_of_individuals {
+                    return Err(format!("Custom offspring rounding returned {} amounts summing up to {} \
+                                        instead of {} summing up to {}",
+                                       amounts.len(), amounts.iter().sum::<usize>(), shares.len(), number_of_individuals));
+                }
+                for (amount, species) in amounts.iter_mut().zip(self.species_collection.iter()) {
+                    if self.locked_species.contains(&species.id) {
+                        *amount = 0;
+                    }
+                }
+            }
+        }
+        Ok(amounts)
     }
 
     /// `species_offspring_amount` could be incorrect because of approximation errors when we round floats to integers.
@@ -334,16 +1325,22 @@ where
     /// It adds (or removes if negative) the `missing_offspring` number of individuals in the vector.
     /// When adding, it chooses the best species.
     /// When removing, it chooses the worst species, multiple species if one species is not big enough.
+    /// No species is reduced below its `minimum_amounts` entry.
     ///
     /// @param species_offspring_amount vector of offspring_amounts that needs correction
+    /// @param minimum_amounts minimum offspring amount of every species, their sum must not exceed the target
     /// @param missing_offspring amount of correction to be done. Positive means we need more offsprings, negative means
     /// we have to much.
-    fn correct_population_size(&mut self, species_offspring_amount: &mut Vec<usize>, missing_offspring: i32)
+    fn correct_population_size(&mut self, species_offspring_amount: &mut [usize], minimum_amounts: &[usize], missing_offspring: i32)
     {
         // positive means lacking individuals
         if missing_offspring > 0
         {
-            let i: usize = self.species_collection.get_best().expect("a best species to be found");
+            let mut i: usize = self.species_collection.get_best().expect("a best species to be found");
+            if self.locked_species.contains(&self.species_collection.get(i).id) {
+                i = self.species_collection.get_best_excluding(&self.locked_species)
+                    .expect("a best unlocked species to be found");
+            }
             species_offspring_amount[i] += missing_offspring as usize;
         }
         // negative have excess individuals
@@ -351,20 +1348,21 @@ where
         {
             // remove missing number of individuals
             let mut excess_offspring = (-missing_offspring) as usize;
-            let mut excluded_id_list= HashSet::<usize>::new();
+            let mut excluded_id_list= self.locked_species.clone();
 
             while excess_offspring > 0 {
                 let (worst_species_i, worst_species) = self.species_collection
                     .get_worst(1, Some(&excluded_id_list)).expect("Couldn't find the worst species");
 
+                let minimum_amount = minimum_amounts[worst_species_i];
                 let mut current_amount = species_offspring_amount[worst_species_i];
 
-                if current_amount > excess_offspring {
+                if current_amount - minimum_amount > excess_offspring {
                     current_amount -= excess_offspring;
                     excess_offspring = 0;
                 } else {
-                    excess_offspring -= current_amount;
-                    current_amount = 0;
+                    excess_offspring -= current_amount - minimum_amount;
+                    current_amount = minimum_amount;
                 }
 
                 species_offspring_amount[worst_species_i] = current_amount;
@@ -379,6 +1377,17 @@ where
         }
     }
 
+    /// Creates the genus of the next generation from the evaluated new individuals.
+    ///
+    /// `conf.total_population_size` can differ from the size the individuals were generated for:
+    /// when growing, the extra slots are allocated proportionally to the species and filled by the
+    /// population management from the old individuals; when shrinking, the worst individuals of each
+    /// species are truncated.
+    ///
+    /// @param conf Species configuration object
+    /// @param generated_individuals new individuals created by `generate_new_individuals`, already evaluated
+    /// @param population_management function to create the new population from the old and new individual
+    /// @return the genus of the next generation
     pub fn next_generation<PopManager>(&mut self,
                            conf: &Conf,
                            generated_individuals: GenusSeed<I, F>,
@@ -388,6 +1397,9 @@ where
     {
         let mut local_next_species_id: usize = self.next_species_id;
 
+        // Release the evaluation pointers, the new individuals are owned by the species and orphans only
+        drop(generated_individuals.need_evaluation);
+
         let mut new_species_collection = SpeciesCollection::new_from_iter(
             generated_individuals.new_species_collection
                 .into_iter()
@@ -398,26 +1410,84 @@ where
         // MANAGE ORPHANS, POSSIBLY CREATE NEW SPECIES
         // recheck if other species can adopt the orphans individuals.
 
-        for orphan in generated_individuals.orphans {
+        for (orphan_i, orphan) in generated_individuals.orphans.into_iter().enumerate() {
             let orphan = Rc::try_unwrap(orphan).unwrap().into_inner();
-            let compatible_species = new_species_collection.iter_mut()
-                .find(|species| species.is_compatible(&orphan));
+            let compatible_species = new_species_collection.find_compatible(&orphan, conf, &self.locked_species);
 
-            if let Some(compatible_species) = compatible_species {
-                compatible_species.insert(orphan);
+            // with OrphanPolicy::NearestSpecies the orphan joins the nearest species other than its parent one
+            let nearest_species = match conf.orphan_policy {
+                OrphanPolicy::NearestSpecies => {
+                    let mut exclude = self.locked_species.clone();
+                    exclude.extend(generated_individuals.orphan_species_ids.get(orphan_i));
+                    new_species_collection.nearest_by_distance(&orphan, &exclude)
+                }
+                _ => None,
+            };
+
+            if let Some(compatible_species) = compatible_species.or(nearest_species) {
+                new_species_collection.get_mut(compatible_species).insert(orphan);
+            } else if let Some(nearest) = conf.max_species
+                .filter(|max_species| new_species_collection.len() >= *max_species)
+                .and_then(|_| new_species_collection.nearest(&orphan, &self.locked_species)) {
+                new_species_collection.get_mut(nearest).insert(orphan);
             } else {
-                let new_species = Species::new(orphan, local_next_species_id);
-                local_next_species_id += 1;
+                let species_id = new_species_collection.allocate_species_id(conf.species_id_policy, &mut local_next_species_id, &self.locked_species);
+                let new_species = match generated_individuals.orphan_species_ids.get(orphan_i) {
+                    Some(&parent_species_id) => Species::new_descendant(orphan, species_id, parent_species_id),
+                    None => Species::new(orphan, species_id),
+                };
                 new_species_collection.push(new_species);
                 // add an entry for new species which does not have a previous iteration.
                 }
             }
 
-        // Do a recount on the number of offspring per species
-        let new_population_size = 0; //TODO list_of_new_species.count_individuals();
-        let offspring_amounts = self.count_offsprings(conf.total_population_size - new_population_size).unwrap();
+        // Every species keeps as many individuals as the offspring it received (after the orphans moved)
+        // plus its elite slots. The species created from orphans keep their entire population.
+        let elite_slots = generated_individuals.elite_slots;
+        let mut offspring_amounts: Vec<usize> = new_species_collection.iter()
+            .enumerate()
+            .map(|(species_i, species)| species.len() + elite_slots.get(species_i).copied().unwrap_or(0))
+            .collect();
+        let locked_individuals = self.count_locked_individuals();
         // If this assert fails, the next population size is going to be different
-        assert_eq!(offspring_amounts.iter().sum::<usize>(), conf.total_population_size - new_population_size);
+        assert_eq!(offspring_amounts.iter().sum::<usize>() + locked_individuals,
+                   generated_individuals.population_size);
+        assert!(locked_individuals < conf.total_population_size,
+                "locked species occupy the entire population, nothing can reproduce");
+
+        // The best individual of the genus and the index of its species
+        let champion: Option<(usize, I)> = if conf.preserve_champion {
+            self.species_collection.get_best_excluding(&self.locked_species)
+                .and_then(|species_i| self.species_collection.get(species_i).get_best_individual()
+                    .map(|champion| (species_i, champion.clone())))
+        } else {
+            None
+        };
+
+        // Pointers to current const species_collection
+        let old_species_individuals: Vec<Vec<I>> = self.species_collection.iter_mut()
+            .map(|species| species.drain_individuals().collect())
+            .collect();
+
+        // The population size changed after the new individuals were generated:
+        // growing gives the extra slots proportionally to the species (taken from their old individuals),
+        // shrinking truncates the worst individuals of every species.
+        let shrinking = conf.total_population_size < generated_individuals.population_size;
+        let base_amounts = offspring_amounts.clone();
+        if conf.total_population_size != generated_individuals.population_size {
+            let capacities: Vec<usize> = new_species_collection.iter()
+                .enumerate()
+                .map(|(species_i, species)| if self.locked_species.contains(&species.id) {
+                    0
+                } else {
+                    species.len() + old_species_individuals.get(species_i).map_or(0, |old| old.len())
+                })
+                .collect();
+            offspring_amounts = rescale_offspring_amounts(
+                &offspring_amounts,
+                &capacities,
+                conf.total_population_size - locked_individuals);
+        }
 
 
         //////////////////////////////////////////////
@@ -425,7 +1495,7 @@ where
         // update the species population, based ont he population management algorithm.
         for (species_i, (new_species, old_species_individuals))
         in new_species_collection.iter_mut()
-            .zip(generated_individuals.old_species_individuals.into_iter())
+            .zip(old_species_individuals)
             .enumerate()
         {
             if species_i > self.species_collection.len() {
@@ -436,23 +1506,63 @@ where
             }
             println!("POPULATION MANAGEMENT {}", species_i);
 
+            // locked species are frozen, they skip the population management
+            if self.locked_species.contains(&new_species.id) {
+                new_species.set_individuals(old_species_individuals.into_iter());
+                continue;
+            }
+
             // this empties the new_species list
             println!("POPULATION MANAGEMENT {} transform", species_i);
             let new_species_individuals = new_species.drain_individuals().collect();
 
+            let target_amount = if shrinking { base_amounts[species_i] } else { offspring_amounts[species_i] };
+            let mut old_species_individuals = old_species_individuals;
+            let elites = take_elites(&mut old_species_individuals, elite_slots.get(species_i).copied().unwrap_or(0).min(target_amount));
+
             println!("POPULATION MANAGEMENT {} lambda call", species_i);
             // Create next population
             let new_individuals = population_management(
                 new_species_individuals,
                 old_species_individuals,
-                offspring_amounts[species_i]);
+                target_amount - elites.len());
 
-            new_species.set_individuals(new_individuals.into_iter());
+            new_species.set_individuals(elites.into_iter().chain(new_individuals));
 
             println!("POPULATION MANAGEMENT {} done", species_i);
         }
 
 
+        if shrinking {
+            for (new_species, amount) in new_species_collection.iter_mut().zip(&offspring_amounts) {
+                if !self.locked_species.contains(&new_species.id) {
+                    new_species.retain_best(*amount);
+                }
+            }
+        }
+
+        // The species of the champion received no offspring, the champion takes the place of the
+        // worst individual of the worst species, even if it is the last one of that species
+        if let Some((champion_species_i, champion)) = champion {
+            if new_species_collection.get(champion_species_i).is_empty() {
+                let mut excluded_id_list = self.locked_species.clone();
+                excluded_id_list.insert(new_species_collection.get(champion_species_i).id);
+                let donor_i = new_species_collection.get_worst(1, Some(&excluded_id_list))
+                    .map(|(donor_i, _)| donor_i)
+                    // species without an evaluated individual are not ranked, the largest one gives the slot
+                    .or_else(|| new_species_collection.iter()
+                        .enumerate()
+                        .filter(|(_, species)| !excluded_id_list.contains(&species.id))
+                        .max_by_key(|(_, species)| species.len())
+                        .map(|(donor_i, _)| donor_i))
+                    .expect("the unlocked species to hold the offspring");
+                let donor_species = new_species_collection.get_mut(donor_i);
+                let donor_size = donor_species.len();
+                donor_species.retain_best(donor_size - 1);
+                new_species_collection.get_mut(champion_species_i).insert(champion);
+            }
+        }
+
         //////////////////////////////////////////////
         // ASSERT SECTION
         // check for duplicated species IDs
@@ -462,6 +1572,21 @@ where
 
         new_species_collection.cleanup();
 
+        if conf.merge_compatible_species {
+            new_species_collection.merge_compatible(conf, &self.locked_species);
+        }
+
+        new_species_collection.split_oversized(conf, &self.locked_species, &mut local_next_species_id);
+
+        let mut species_parents = self.species_parents.clone();
+        for species in new_species_collection.iter() {
+            // a reused id gets the lineage of the new species
+            match species.parent_species_id() {
+                Some(parent_species_id) => species_parents.insert(species.id, parent_species_id),
+                None => species_parents.remove(&species.id),
+            };
+        }
+
         // Assert species list size and number of individuals
         let n_individuals: usize = new_species_collection.count_individuals();
         if n_individuals != conf.total_population_size {
@@ -469,8 +1594,118 @@ where
                 n_individuals, conf.total_population_size);
         }
 
+        // the locked species that died out or were merged away must not keep their ids reserved
+        let locked_species: HashSet<usize> = new_species_collection.iter()
+            .map(|species| species.id)
+            .filter(|species_id| self.locked_species.contains(species_id))
+            .collect();
+
         //////////////////////////////////////////////
         // CREATE THE NEXT GENUS
-        Genus::build_next_generation(new_species_collection, local_next_species_id)
+        Genus::build_next_generation(self.generation + 1,
+                                     new_species_collection,
+                                     local_next_species_id,
+                                     locked_species,
+                                     self.best_fitness,
+                                     self.generations_without_improvement,
+                                     self.hypermutation_factor,
+                                     self.last_mass_extinction,
+                                     self.fitness_collapses,
+                                     self.speciation_fallback,
+                                     species_parents,
+                                     self.fork_rng())
+    }
+}
+
+/// Matrix of the distances between all the individuals, None if `Individual::distance` is not available
+fn pairwise_distances<'a, I, F, It>(individuals: It) -> Option<Vec<Vec<f64>>>
+where
+    I: 'a + Individual<F>,
+    F: num::Float,
+    It: Iterator<Item=&'a I>,
+{
+    let individuals: Vec<&I> = individuals.collect();
+    let mut distances = vec![vec![0.0; individuals.len()]; individuals.len()];
+    for (i, a) in individuals.iter().enumerate() {
+        for (j, b) in individuals.iter().enumerate().skip(i + 1) {
+            let distance = a.distance(b)?.to_f64()?;
+            distances[i][j] = distance;
+            distances[j][i] = distance;
+        }
+    }
+    Some(distances)
+}
+
+/// How the parents of the offspring are chosen by `Genus::generate_new_individuals_core`
+enum ParentSelection<'f, SelectionF, ParentSelectionF, BatchSelectionF> {
+    /// The parents of every offspring on their own, by the `selection` and `parent_selection` functions
+    Single(&'f mut SelectionF, &'f mut ParentSelectionF),
+    /// The parents of all the offspring of a species at once, by the `batch_selection` function
+    Batch(&'f mut BatchSelectionF),
+}
+
+/// Types of the selection functions left out of a `ParentSelection`
+type SelectionFn<I, F> = for<'s> fn(Box<SpeciesIter<'s, I, F>>) -> &'s I;
+type ParentSelectionFn<I, F> = for<'s> fn(Box<SpeciesIter<'s, I, F>>) -> (&'s I, &'s I);
+type BatchSelectionFn<I, F> = for<'s> fn(Box<SpeciesIter<'s, I, F>>, usize) -> Vec<&'s I>;
+
+/// Removes the `amount` best individuals (by fitness) from `individuals` and returns them.
+fn take_elites<I: Individual<F>, F: num::Float>(individuals: &mut Vec<I>, amount: usize) -> Vec<I> {
+    individuals.sort_by(|a, b| b.fitness().partial_cmp(&a.fitness()).unwrap_or(std::cmp::Ordering::Equal));
+    let amount = amount.min(individuals.len());
+    individuals.drain(..amount).collect()
+}
+
+/// Adds the `missing` offspring, one each, to the amounts with the largest fractional remainders
+///
+/// @param remainders index and fractional remainder of the share of every species with a positive remainder
+fn add_to_largest_remainders<F: num::Float>(amounts: &mut [usize], mut remainders: Vec<(usize, F)>, missing: usize) {
+    remainders.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    for (i, _) in remainders.into_iter().take(missing) {
+        amounts[i] += 1;
     }
-}
\ No newline at end of file
+}
+
+/// Scales the `amounts` so that they sum up to `target`, proportionally to their current value.
+/// No amount is scaled above its capacity, the slots that do not fit are given to the other entries
+/// (largest first).
+///
+/// Panics if the capacities do not sum up to at least `target`.
+fn rescale_offspring_amounts(amounts: &[usize], capacities: &[usize], target: usize) -> Vec<usize> {
+    let total: usize = amounts.iter().sum();
+    let mut scaled: Vec<usize> = amounts.iter()
+        .zip(capacities)
+        .map(|(&amount, &capacity)| (amount * target).checked_div(total).unwrap_or(0).min(capacity))
+        .collect();
+
+    let mut order: Vec<usize> = (0..amounts.len()).collect();
+    order.sort_by(|&a, &b| amounts[b].cmp(&amounts[a]));
+
+    let mut missing = target - scaled.iter().sum::<usize>();
+    while missing > 0 {
+        let mut assigned = false;
+        for &i in order.iter() {
+            if missing == 0 {
+                break;
+            }
+            if scaled[i] < capacities[i] {
+                scaled[i] += 1;
+                missing -= 1;
+                assigned = true;
+            }
+        }
+        assert!(assigned, "Not enough individuals to scale the population to {}", target);
+    }
+
+    scaled
+}
+
+impl<I, F> Default for Genus<I, F>
+where
+    I: Individual<F> + Debug,
+    F: num::Float + Debug + std::iter::Sum,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -19,16 +19,52 @@ use std::borrow::BorrowMut;
 use std::cell::RefCell;
 use std::fmt::Debug;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
+use crate::population;
 use crate::speciation::Individual;
 use num::Float;
 use crate::speciation::species::RcSpecies;
-use crate::speciation::species_collection::SpeciesCollection;
+
+/// Outcome of the evaluation trials of a single individual
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrialReport<F: Float> {
+    /// number of trials performed
+    pub trials: usize,
+    /// mean fitness across the trials
+    pub mean: F,
+    /// variance of the fitness across the trials
+    pub variance: F,
+}
 
 pub struct GenusSeed<I: Individual<F>, F: Float> {
     pub orphans: Vec<Rc<RefCell<I>>>,
+    /// Id of the species each orphan (same index) was generated from
+    pub orphan_species_ids: Vec<usize>,
     pub new_species_collection: Vec<RcSpecies<I,F>>,
     pub need_evaluation: Vec<Rc<RefCell<I>>>,
-    pub old_species_individuals: Vec<Vec<I>>
+    /// Number of evaluation trials for each individual in `need_evaluation` (same index)
+    pub trial_budgets: Vec<usize>,
+    /// Results of the trials of each individual in `need_evaluation` (same index),
+    /// filled by `evaluate_trials`
+    pub trial_reports: Vec<TrialReport<F>>,
+    /// Total population size the new individuals were generated for
+    pub population_size: usize,
+    /// How many times the crossover compatibility guard fell back to asexual reproduction
+    pub crossover_guard_triggers: usize,
+    /// How many species got no offspring because none of their individuals was evaluated
+    /// (see `UnevaluatedSpeciesPolicy::Skip`)
+    pub skipped_species: usize,
+    /// How many duplicate offspring were rejected and re-generated (see `Conf::duplicate_offspring_retries`)
+    pub duplicate_offspring_rejections: usize,
+    /// How many incompatible offspring were re-generated (see `OrphanPolicy::Regenerate`)
+    pub orphan_regenerations: usize,
+    /// How many times a fallible reproduction or crossover failed (see `Genus::generate_new_individuals_fallible`)
+    pub operator_failures: usize,
+    /// How many offspring are clones of their parent because the operator failed every retry
+    pub operator_fallbacks: usize,
+    /// Slots of every species (same index as `new_species_collection`) reserved to its elites,
+    /// filled with the best old individuals by `Genus::next_generation`
+    pub elite_slots: Vec<usize>,
 }
 
 impl<I: Individual<F>, F: Float+Debug> GenusSeed<I,F> {
@@ -36,21 +72,91 @@ impl<I: Individual<F>, F: Float+Debug> GenusSeed<I,F> {
         orphans: Vec<Rc<RefCell<I>>>,
         new_species_collection: Vec<RcSpecies<I,F>>,
         need_evaluation: Vec<Rc<RefCell<I>>>,
-        old_species_individuals: Vec<Vec<I>>) -> Self {
+        population_size: usize) -> Self {
+        let trial_budgets = vec![1; need_evaluation.len()];
         Self {
             orphans,
+            orphan_species_ids: Vec::new(),
             new_species_collection,
             need_evaluation,
-            old_species_individuals,
+            trial_budgets,
+            trial_reports: Vec::new(),
+            population_size,
+            crossover_guard_triggers: 0,
+            skipped_species: 0,
+            duplicate_offspring_rejections: 0,
+            orphan_regenerations: 0,
+            operator_failures: 0,
+            operator_fallbacks: 0,
+            elite_slots: Vec::new(),
         }
     }
 
     pub fn evaluate<E: FnMut(&mut I) -> F >(&mut self, mut evaluate_individual: E) {
-        for mut new_individual in self.need_evaluation.iter_mut() {
-            let fitness: F = evaluate_individual(new_individual.as_ref().borrow_mut().borrow_mut());
-            let individual_fitness = new_individual.borrow().fitness();
-            assert!(individual_fitness.is_some());
-            assert_eq!(fitness, individual_fitness.unwrap());
+        for new_individual in self.need_evaluation.iter_mut() {
+            population::evaluate_individual(&mut *new_individual.as_ref().borrow_mut(), &mut evaluate_individual);
+        }
+    }
+
+    /// Evaluates the new individuals on a set of test cases (see `selection::Lexicase`).
+    ///
+    /// `evaluate_individual` has to return the score of the individual on every case, store them so that
+    /// `Individual::case_scores` returns them and set the aggregated fitness on the individual.
+    pub fn evaluate_cases<E: FnMut(&mut I) -> Vec<F>>(&mut self, mut evaluate_individual: E) {
+        for new_individual in self.need_evaluation.iter() {
+            let scores: Vec<F> = evaluate_individual(new_individual.as_ref().borrow_mut().borrow_mut());
+            let individual = new_individual.borrow();
+            assert!(individual.fitness().is_some());
+            assert_eq!(individual.case_scores(), Some(scores.as_slice()));
+        }
+    }
+
+    /// Evaluates the new individuals that have no fitness yet until `time_budget` runs out, so a generation
+    /// can fit in a fixed wall-clock window. Calling it again resumes from the first individual not evaluated.
+    ///
+    /// The individuals left without fitness are carried into the next generation by `Genus::next_generation`,
+    /// call `Genus::ensure_evaluated_population` to evaluate them before the next `Genus::update`.
+    ///
+    /// @return the number of individuals evaluated
+    pub fn evaluate_within<E: FnMut(&mut I) -> F>(&mut self, time_budget: Duration, mut evaluate_individual: E) -> usize {
+        let start = Instant::now();
+        let mut evaluated: usize = 0;
+        for new_individual in self.need_evaluation.iter() {
+            if new_individual.borrow().fitness().is_some() {
+                continue;
+            }
+            if start.elapsed() >= time_budget {
+                break;
+            }
+            population::evaluate_individual(&mut *new_individual.as_ref().borrow_mut(), &mut evaluate_individual);
+            evaluated += 1;
+        }
+        evaluated
+    }
+
+    /// Number of new individuals still without fitness
+    pub fn pending_evaluations(&self) -> usize {
+        self.need_evaluation.iter()
+            .filter(|new_individual| new_individual.borrow().fitness().is_none())
+            .count()
+    }
+
+    /// Evaluates the new individuals giving each of them its trial budget (see `Conf::trial_budget`).
+    ///
+    /// `evaluate_individual` receives the individual and the number of trials to perform, it has to
+    /// return the fitness of each trial and to set the aggregated fitness on the individual.
+    /// Mean and variance of the trials are recorded in `trial_reports`.
+    pub fn evaluate_trials<E: FnMut(&mut I, usize) -> Vec<F>>(&mut self, mut evaluate_individual: E) {
+        self.trial_reports.clear();
+        for (new_individual, &trials) in self.need_evaluation.iter().zip(self.trial_budgets.iter()) {
+            let samples: Vec<F> = evaluate_individual(new_individual.as_ref().borrow_mut().borrow_mut(), trials);
+            assert_eq!(samples.len(), trials);
+            assert!(new_individual.borrow().fitness().is_some());
+
+            let n = F::from(samples.len().max(1)).unwrap();
+            let mean = samples.iter().fold(F::zero(), |acc, &f| acc + f) / n;
+            let variance = samples.iter().fold(F::zero(), |acc, &f| acc + (f - mean) * (f - mean)) / n;
+            self.trial_reports.push(TrialReport { trials, mean, variance });
         }
     }
 }
\ No newline at end of file
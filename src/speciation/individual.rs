@@ -15,8 +15,113 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::speciation::Conf;
 
 pub trait Individual<F: num::Float>: Clone {
     fn fitness(&self) -> Option<F>;
     fn is_compatible(&self, other: &Self) -> bool;
+
+    /// Real-valued (genotypic) distance between two individuals.
+    ///
+    /// Implementing it is optional: when it returns `None` (the default) only `is_compatible` is used.
+    fn distance(&self, _other: &Self) -> Option<F> {
+        None
+    }
+
+    /// Hash of the genome, equal for genetically identical individuals.
+    ///
+    /// Implementing it is optional: it is used to reject duplicate offspring
+    /// (see `Conf::duplicate_offspring_retries`), which is disabled when it returns `None` (the default).
+    fn genome_hash(&self) -> Option<u64> {
+        None
+    }
+
+    /// Score of the individual on every test case (higher is better), e.g. for program synthesis.
+    ///
+    /// Implementing it is optional: it is used by `selection::Lexicase`, see also `GenusSeed::evaluate_cases`.
+    fn case_scores(&self) -> Option<&[F]> {
+        None
+    }
+}
+
+/// Checks if two individuals are compatible (belong to the same species).
+///
+/// When `conf.compatibility_threshold` is set and the individuals provide a `distance`, they are compatible
+/// if their distance is not greater than the threshold. Otherwise `Individual::is_compatible` is used.
+pub fn are_compatible<I: Individual<F>, F: num::Float>(a: &I, b: &I, conf: &Conf) -> bool {
+    if let Some(threshold) = conf.compatibility_threshold {
+        if let Some(distance) = a.distance(b) {
+            return distance <= F::from(threshold).unwrap();
+        }
+    }
+    a.is_compatible(b)
+}
+
+/// Checks if two individuals can be the parents of a crossover, for `conf.crossover_compatibility_guard`.
+///
+/// When `conf.crossover_distance_threshold` is set and the individuals provide a `distance`, they can
+/// if their distance is not greater than the threshold. Otherwise `are_compatible` is used.
+pub fn are_crossover_compatible<I: Individual<F>, F: num::Float>(a: &I, b: &I, conf: &Conf) -> bool {
+    if let Some(threshold) = conf.crossover_distance_threshold {
+        if let Some(distance) = a.distance(b) {
+            return distance <= F::from(threshold).unwrap();
+        }
+    }
+    are_compatible(a, b, conf)
+}
+
+/// Wrapper turning a minimization problem into the maximization the crate works with: the fitness of
+/// the wrapped individual is a cost, `Minimize` exposes a positive fitness decreasing with the cost
+/// (see `cost_to_fitness`), so it works with the default `NegativeFitnessPolicy::Reject`.
+///
+/// The case scores (see `Individual::case_scores`) are forwarded unchanged: they are higher-is-better,
+/// so the wrapped individual has to provide them in that direction (e.g. the negated errors).
+#[derive(Clone, Debug)]
+pub struct Minimize<I>(pub I);
+
+impl<I> Minimize<I> {
+    /// Evaluates the wrapped individual with `evaluate_cost`, which has to set its cost as its fitness.
+    /// Use it in the evaluation closures, e.g. `seed.evaluate(|individual| individual.evaluate(evaluate_cost))`.
+    ///
+    /// @return the fitness (see `cost_to_fitness`)
+    pub fn evaluate<F: num::Float, E: FnOnce(&mut I) -> F>(&mut self, evaluate_cost: E) -> F {
+        cost_to_fitness(evaluate_cost(&mut self.0))
+    }
+
+    /// Cost of the wrapped individual (its own fitness)
+    pub fn cost<F: num::Float>(&self) -> Option<F> where I: Individual<F> {
+        self.0.fitness()
+    }
+}
+
+/// Fitness of a cost for `Minimize`: `1 / (1 + cost)` for a non negative cost and `1 - cost` for a
+/// negative one. It is always positive and it strictly decreases as the cost grows.
+pub fn cost_to_fitness<F: num::Float>(cost: F) -> F {
+    if cost >= F::zero() {
+        F::one() / (F::one() + cost)
+    } else {
+        F::one() - cost
+    }
+}
+
+impl<I: Individual<F>, F: num::Float> Individual<F> for Minimize<I> {
+    fn fitness(&self) -> Option<F> {
+        self.0.fitness().map(cost_to_fitness)
+    }
+
+    fn is_compatible(&self, other: &Self) -> bool {
+        self.0.is_compatible(&other.0)
+    }
+
+    fn distance(&self, other: &Self) -> Option<F> {
+        self.0.distance(&other.0)
+    }
+
+    fn genome_hash(&self) -> Option<u64> {
+        self.0.genome_hash()
+    }
+
+    fn case_scores(&self) -> Option<&[F]> {
+        self.0.case_scores()
+    }
 }
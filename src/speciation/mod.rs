@@ -16,10 +16,12 @@
  */
 
 pub use age::Age;
-pub use conf::Conf;
+pub use conf::{AdjustedFitnessMode, AgeFitnessAdjustment, Conf, FitnessCollapsePolicy, FitnessSharing, Hypermutation, MassExtinction, MutationSchedule, NegativeFitnessPolicy, OffspringRounding, OrphanPolicy, RepresentativePolicy, SpeciationStrategy, SpeciesIdPolicy, StagnationPolicy, TrialBudget, UnevaluatedSpeciesPolicy};
 pub use genus::Genus;
-pub use individual::Individual;
-pub use species::Species;
+pub use genus_seed::{GenusSeed, TrialReport};
+pub use individual::{are_compatible, are_crossover_compatible, cost_to_fitness, Individual, Minimize};
+pub use reproduction_operators::{OperatorError, ReproductionOperators};
+pub use species::{Species, SpeciesIter};
 
 mod age;
 mod conf;
@@ -27,6 +29,7 @@ mod individual;
 mod genus;
 mod species;
 mod population_management;
-mod species_collection;
+pub(crate) mod species_collection;
 mod genus_seed;
+mod reproduction_operators;
 
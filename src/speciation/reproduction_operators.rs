@@ -0,0 +1,61 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::error::Error;
+use std::fmt;
+
+use crate::speciation::{Individual, SpeciesIter};
+
+/// Failure of a reproduction or crossover function, see `Genus::generate_new_individuals_fallible`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OperatorError {
+    pub message: String,
+}
+
+impl OperatorError {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl fmt::Display for OperatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "reproduction operator failed: {}", self.message)
+    }
+}
+
+impl Error for OperatorError {}
+
+/// The five operators `Genus::generate_new_individuals` needs, bundled in one implementor
+/// for `Genus::generate_new_individuals_with`
+pub trait ReproductionOperators<I: Individual<F>, F: num::Float> {
+    /// Selects 1 parent (called even if crossover is chosen, when there is not more than one parent
+    /// possible, and for each of the two parents of an interspecies crossover)
+    fn select<'s>(&mut self, individuals: Box<SpeciesIter<'s, I, F>>) -> &'s I;
+
+    /// Selects 2 parents (only called if crossover is chosen, see `Conf::crossover_probability`)
+    fn select_parents<'s>(&mut self, individuals: Box<SpeciesIter<'s, I, F>>) -> (&'s I, &'s I);
+
+    /// Creates a new individual from 1 parent
+    fn reproduce(&mut self, parent: &I) -> I;
+
+    /// Creates a new individual from 2 parents
+    fn crossover(&mut self, parent1: &I, parent2: &I) -> I;
+
+    /// Mutates a new individual
+    fn mutate(&mut self, individual: &mut I);
+}
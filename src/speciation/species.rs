@@ -15,17 +15,19 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::cell::RefCell;
+use std::cell::{OnceCell, RefCell};
+use std::collections::VecDeque;
 use std::cmp::Ordering;
 use std::fmt::Debug;
-use std::iter::Map;
 use std::rc::Rc;
 // use std::iter::{Chain, Cloned, Copied, Cycle, Enumerate, Filter, FilterMap, FlatMap, Flatten, FromIterator, Fuse, Inspect, Intersperse, IntersperseWith, Iterator, Map, MapWhile, Peekable, Product, Rev, Scan, Skip, SkipWhile, StepBy, Sum, Take, TakeWhile, TrustedRandomAccessNoCoerce, Zip};
 // use std::ops::{Residual, Try};
 use std::slice::{Iter, IterMut};
-use std::vec::Drain;
 
-use crate::speciation::{Age, Conf, Individual};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::speciation::{are_compatible, Age, Conf, Individual, RepresentativePolicy, StagnationPolicy, UnevaluatedSpeciesPolicy};
 
 // #[derive(Clone)]
 struct Indiv<I: Individual<F>, F: num::Float> {
@@ -44,34 +46,64 @@ impl<I: Individual<F>, F: num::Float> From<I> for Indiv<I, F> {
 
 pub struct Species<I: Individual<F>, F: num::Float> {
     individuals: Vec<Indiv<I, F>>,
+    representative: I,
     pub id: usize,
+    /// Species the founder of this species came from, None for the species of the initial population
+    parent_species_id: Option<usize>,
     age: Age,
     last_best_fitness: F,
+    /// Best fitness of every generation (oldest first), None for the generations without evaluated individuals
+    fitness_history: VecDeque<Option<F>>,
+    /// Indices of the individuals in descending fitness order, computed by `iter_sorted`
+    /// and invalidated every time the individuals change
+    sorted_order: OnceCell<Vec<usize>>,
 }
 
 impl<I: Individual<F>, F: num::Float + std::iter::Sum> Species<I, F> {
     pub fn new(individual: I, species_id: usize) -> Self {
         Self {
+            representative: individual.clone(),
             individuals: vec![Indiv::from(individual)],
             id: species_id,
+            parent_species_id: None,
             age: Age::new(),
-            last_best_fitness: F::zero(),
+            last_best_fitness: F::neg_infinity(),
+            fitness_history: VecDeque::new(),
+            sorted_order: OnceCell::new(),
         }
     }
 
-    pub fn clone_with_new_individuals<It>(&self, new_individuals: It) -> RcSpecies<I,F>
-        where It: Iterator<Item=Rc<RefCell<I>>> {
+    /// Creates the species for the next generation with the new individuals.
+    /// The representative is chosen among the current individuals following `conf.representative_policy`.
+    pub fn clone_with_new_individuals<It, R>(&self, new_individuals: It, conf: &Conf, rng: &mut R) -> RcSpecies<I,F>
+        where It: Iterator<Item=Rc<RefCell<I>>>, R: Rng + ?Sized {
         RcSpecies {
             individuals: new_individuals.collect(),
+            representative: self.choose_representative(conf.representative_policy, rng).clone(),
             id: self.id,
+            parent_species_id: self.parent_species_id,
             age: self.age.clone(),
-            last_best_fitness: self.last_best_fitness.clone(),
+            last_best_fitness: self.last_best_fitness,
+            fitness_history: self.fitness_history.clone(),
         }
     }
 
-    pub fn is_compatible(&self, candidate: &I) -> bool {
+    /// Creates a new species founded by `individual`, which came from the species `parent_species_id`
+    pub fn new_descendant(individual: I, species_id: usize, parent_species_id: usize) -> Self {
+        let mut species = Self::new(individual, species_id);
+        species.parent_species_id = Some(parent_species_id);
+        species
+    }
+
+    /// Species the founder of this species came from (see `Genus::lineage`),
+    /// None for the species of the initial population
+    pub fn parent_species_id(&self) -> Option<usize> {
+        self.parent_species_id
+    }
+
+    pub fn is_compatible(&self, candidate: &I, conf: &Conf) -> bool {
         if let Some(representative) = self.representative() {
-            representative.is_compatible(candidate)
+            are_compatible(representative, candidate, conf)
         } else {
             false
         }
@@ -85,8 +117,69 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> Species<I, F> {
 
     pub fn get_best_fitness(&self) -> Option<F> {
         self.get_best_individual()
-            .map(|i| i.fitness())
-            .flatten()
+            .and_then(|i| i.fitness())
+    }
+
+    /// Fitness of the evaluated individuals
+    fn evaluated_fitnesses(&self) -> Vec<F> {
+        self.individuals.iter()
+            .filter_map(|indiv| indiv.individual.fitness())
+            .collect()
+    }
+
+    /// Mean fitness of the evaluated individuals, None if no individual is evaluated
+    pub fn mean_fitness(&self) -> Option<F> {
+        let fitnesses = self.evaluated_fitnesses();
+        if fitnesses.is_empty() {
+            return None;
+        }
+        Some(fitnesses.iter().copied().sum::<F>() / F::from(fitnesses.len()).unwrap())
+    }
+
+    /// Median fitness of the evaluated individuals (mean of the two central values for an even number),
+    /// None if no individual is evaluated
+    pub fn median_fitness(&self) -> Option<F> {
+        let mut fitnesses = self.evaluated_fitnesses();
+        if fitnesses.is_empty() {
+            return None;
+        }
+        fitnesses.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let middle = fitnesses.len() / 2;
+        if fitnesses.len() % 2 == 0 {
+            Some((fitnesses[middle - 1] + fitnesses[middle]) / F::from(2).unwrap())
+        } else {
+            Some(fitnesses[middle])
+        }
+    }
+
+    /// Standard deviation (population) of the fitness of the evaluated individuals,
+    /// None if no individual is evaluated
+    pub fn std_fitness(&self) -> Option<F> {
+        let mean = self.mean_fitness()?;
+        let fitnesses = self.evaluated_fitnesses();
+        let variance = fitnesses.iter()
+            .map(|fitness| (*fitness - mean) * (*fitness - mean))
+            .sum::<F>() / F::from(fitnesses.len()).unwrap();
+        Some(variance.sqrt())
+    }
+
+    /// Lowest fitness of the evaluated individuals, None if no individual is evaluated
+    pub fn min_fitness(&self) -> Option<F> {
+        self.evaluated_fitnesses().into_iter().reduce(F::min)
+    }
+
+    /// Highest fitness of the evaluated individuals, None if no individual is evaluated
+    pub fn max_fitness(&self) -> Option<F> {
+        self.evaluated_fitnesses().into_iter().reduce(F::max)
+    }
+
+    /// Mean adjusted fitness of the individuals, None if it was not computed (see `Genus::update`)
+    pub fn mean_adjusted_fitness(&self) -> Option<F> {
+        let adjusted_fitnesses: Option<Vec<F>> = self.individuals.iter()
+            .map(|indiv| indiv.adjusted_fitness)
+            .collect();
+        let adjusted_fitnesses = adjusted_fitnesses.filter(|fitnesses| !fitnesses.is_empty())?;
+        Some(adjusted_fitnesses.iter().copied().sum::<F>() / F::from(adjusted_fitnesses.len()).unwrap())
     }
 
     /// This method performs fitness sharing. It computes the adjusted fitness of the individuals.
@@ -95,23 +188,51 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> Species<I, F> {
     /// # Arguments
     ///
     /// * `is_best_species` set to true if this is the best species
+    /// * `shared_value` maps the fitness of an individual to the non-negative value shared in the species
+    /// * `niche_counts` what the value of every individual (same index) is divided by, the species size if None
     ///
-    pub fn compute_adjust_fitness(&mut self, is_best_species: bool, conf: &Conf) {
+    /// @return true if the species was skipped because no individual is evaluated (see `UnevaluatedSpeciesPolicy::Skip`)
+    pub fn compute_adjust_fitness(&mut self, is_best_species: bool, shared_value: &dyn Fn(F) -> F, niche_counts: Option<&[F]>, conf: &Conf) -> bool {
         assert!(!self.is_empty());
 
         let individual_n = self.individuals.len();
 
+        if self.individuals.iter().all(|indiv| indiv.individual.fitness().is_none()) {
+            match conf.unevaluated_species_policy {
+                UnevaluatedSpeciesPolicy::TreatAsZero => {}
+                UnevaluatedSpeciesPolicy::Skip => {
+                    for indiv in &mut self.individuals {
+                        indiv.adjusted_fitness = Some(F::zero());
+                    }
+                    return true;
+                }
+                UnevaluatedSpeciesPolicy::Panic => {
+                    panic!("Species {} has no evaluated individuals", self.id);
+                }
+            }
+        }
+
         // Iterates through individuals and sets the adjusted fitness
-        for indiv in &mut self.individuals {
+        for (i, indiv) in self.individuals.iter_mut().enumerate() {
             let fitness = indiv.individual.fitness().unwrap_or(F::zero());
 
-            if fitness < F::zero() {
+            let value = shared_value(fitness);
+            if value < F::zero() {
                 panic!("FITNESS CANNOT BE NEGATIVE");
             }
-            let f_adj: F = Self::individual_adjusted_fitness(fitness, is_best_species, &mut self.age, &mut self.last_best_fitness, conf);
+            let f_adj: F = Self::individual_adjusted_fitness(fitness, value, is_best_species, &mut self.age, &mut self.last_best_fitness, conf);
 
             // Compute the adjusted fitness for this member
-            indiv.adjusted_fitness = Some(f_adj / F::from(individual_n).unwrap());
+            let niche_count = niche_counts.map_or(F::from(individual_n).unwrap(), |niche_counts| niche_counts[i]);
+            indiv.adjusted_fitness = Some(f_adj / niche_count);
+        }
+        false
+    }
+
+    /// Multiplies the adjusted fitness of all the individuals by `factor`
+    pub fn scale_adjusted_fitness(&mut self, factor: F) {
+        for indiv in &mut self.individuals {
+            indiv.adjusted_fitness = indiv.adjusted_fitness.map(|f| f * factor);
         }
     }
 
@@ -123,27 +244,65 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> Species<I, F> {
 
     /// Inserts an individual into this species
     pub fn insert(&mut self, individual: I) {
+        self.sorted_order.take();
         self.individuals.push(Indiv::from(individual))
     }
 
+    /// Keeps only the `amount` best individuals (by fitness), the rest is removed
+    pub fn retain_best(&mut self, amount: usize) {
+        self.sort_by_fitness();
+        self.individuals.truncate(amount);
+    }
+
     /// Replaces set of individuals with a new set of individuals
     pub fn set_individuals<It: Iterator<Item=I>>(&mut self, iterator: It) {
+        self.sorted_order.take();
         self.individuals.clear();
         self.individuals = iterator.into_iter()
             .map(|i| Indiv::from(i))
             .collect()
     }
 
-    pub fn iter(&self) -> SpeciesIter<I,F> {
+    pub fn iter(&self) -> SpeciesIter<'_, I, F> {
         SpeciesIter {
             inner_iterator: self.individuals.iter()
         }
     }
 
+    /// Iterates through the first `amount` individuals of the species
+    pub fn iter_first(&self, amount: usize) -> SpeciesIter<'_, I, F> {
+        SpeciesIter {
+            inner_iterator: self.individuals[..amount.min(self.individuals.len())].iter()
+        }
+    }
+
+    /// Iterates through the individuals in descending fitness order, individuals without fitness last.
+    /// The order is computed once and reused until the individuals change.
+    pub fn iter_sorted(&self) -> impl ExactSizeIterator<Item=&I> + '_ {
+        let order = self.sorted_order.get_or_init(|| {
+            let mut order: Vec<usize> = (0..self.individuals.len()).collect();
+            order.sort_by(|&a, &b| Self::compare_fitness(&self.individuals[a], &self.individuals[b]));
+            order
+        });
+        order.iter().map(move |&i| &self.individuals[i].individual)
+    }
+
+    /// Sorts the individuals by fitness, best first
+    pub fn sort_by_fitness(&mut self) {
+        self.sorted_order.take();
+        self.individuals.sort_by(Self::compare_fitness);
+    }
+
+    /// Descending fitness order, individuals without fitness last
+    fn compare_fitness(a: &Indiv<I, F>, b: &Indiv<I, F>) -> Ordering {
+        b.individual.fitness().partial_cmp(&a.individual.fitness()).unwrap_or(Ordering::Equal)
+    }
+
     // pub fn iter_mut<'a>(&'a mut self) -> Box<dyn ExactSizeIterator<Item=&'a mut I> + 'a> {
     //     Box::new(self.individuals.iter_mut().map(|i| &mut i.individual))
     // }
-    pub fn iter_mut(&mut self) -> SpeciesMutIter<I, F> {
+    pub fn iter_mut(&mut self) -> SpeciesMutIter<'_, I, F> {
+        self.sorted_order.take();
         SpeciesMutIter {
             inner_iterator: self.individuals.iter_mut()
         }
@@ -155,6 +314,33 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> Species<I, F> {
 
     pub fn len(&self) -> usize { self.individuals.len() }
 
+    pub fn age(&self) -> &Age {
+        &self.age
+    }
+
+    /// Appends the current best fitness to the history, dropping the oldest values beyond `max_length`
+    pub fn record_best_fitness(&mut self, max_length: usize) {
+        self.fitness_history.push_back(self.get_best_fitness());
+        while self.fitness_history.len() > max_length {
+            self.fitness_history.pop_front();
+        }
+    }
+
+    /// Best fitness of the last generations (oldest first), up to `Conf::fitness_history_length` values.
+    /// A value is None if the species had no evaluated individual in that generation.
+    pub fn fitness_history(&self) -> &VecDeque<Option<F>> {
+        &self.fitness_history
+    }
+
+    /// Improvement of the best fitness over the last `generations` generations of the history
+    ///
+    /// @return None if the history is too short or one of the two values is missing
+    pub fn fitness_improvement(&self, generations: usize) -> Option<F> {
+        let last = self.fitness_history.len().checked_sub(1)?;
+        let past = last.checked_sub(generations)?;
+        Some((*self.fitness_history.get(last)?)? - (*self.fitness_history.get(past)?)?)
+    }
+
     pub fn increase_generations(&mut self) {
         self.age.increase_generations()
     }
@@ -167,6 +353,10 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> Species<I, F> {
         self.age.increase_no_improvements()
     }
 
+    pub fn reset_no_improvements(&mut self) {
+        self.age.reset_no_improvements();
+    }
+
     pub fn reset_age(&mut self) {
         self.age.reset_generations();
         self.age.reset_no_improvements();
@@ -176,46 +366,137 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> Species<I, F> {
         &self.individuals[index].individual
     }
 
+    /// Adjusted fitness of the individual at `index`, None if it was not computed (see `Genus::update`)
+    pub fn adjusted_fitness(&self, index: usize) -> Option<F> {
+        self.individuals[index].adjusted_fitness
+    }
+
+    /// Iterates through the individuals together with their adjusted fitness (see `adjusted_fitness`),
+    /// e.g. for fitness-sharing aware selection
+    pub fn iter_with_adjusted_fitness(&self) -> impl ExactSizeIterator<Item=(&I, Option<F>)> + '_ {
+        self.individuals.iter().map(|indiv| (&indiv.individual, indiv.adjusted_fitness))
+    }
+
     pub fn individual_mut(&mut self, index: usize) -> &mut I {
+        self.sorted_order.take();
         &mut self.individuals[index].individual
     }
 
     pub fn representative(&self) -> Option<&I> {
-        self.individuals.first().map(|i| &i.individual)
+        Some(&self.representative)
     }
 
-    pub fn drain_individuals(&mut self) -> Map<Drain<'_, Indiv<I, F>>, fn(Indiv<I, F>) -> I> {
-        self.individuals.drain(..)
-            .map(|i| {i.individual})
+    /// Chooses a representative among the individuals of the species.
+    /// If the species is empty, the current representative is kept.
+    fn choose_representative<R: Rng + ?Sized>(&self, policy: RepresentativePolicy, rng: &mut R) -> &I {
+        let first = match self.individuals.first() {
+            Some(first) => &first.individual,
+            None => return &self.representative,
+        };
+        match policy {
+            RepresentativePolicy::First => first,
+            RepresentativePolicy::Random => {
+                &self.individuals.choose(rng).unwrap().individual
+            }
+            RepresentativePolicy::BestFitness => self.get_best_individual().unwrap_or(first),
+            RepresentativePolicy::Medoid => {
+                let mut medoid: Option<(&I, F)> = None;
+                for candidate in self.iter() {
+                    let mut total_distance = F::zero();
+                    for other in self.iter() {
+                        match candidate.distance(other) {
+                            Some(distance) => total_distance = total_distance + distance,
+                            // no distance available
+                            None => return first,
+                        }
+                    }
+                    if medoid.is_none_or(|(_, best_distance)| total_distance < best_distance) {
+                        medoid = Some((candidate, total_distance));
+                    }
+                }
+                medoid.map(|(individual, _)| individual).unwrap_or(first)
+            }
+        }
     }
 
-    fn individual_adjusted_fitness(mut fitness: F, is_best_species: bool, age: &mut Age, last_best_fitness: &mut F, conf: &Conf) -> F {
-        // set small fitness if it is absent
-        if fitness.is_zero() {
-            fitness = F::from(0.0001).unwrap();
+    /// Splits the species in two: the member most distant from the representative becomes the
+    /// representative of the new species and every member goes with the closest of the two.
+    /// The old individuals keep the age of this species, the new species starts young.
+    ///
+    /// @param new_species_id id of the new species
+    /// @return the new species, None if the distance is not available or one of the two halves would be empty
+    pub fn split(&mut self, new_species_id: usize) -> Option<Species<I, F>> {
+        let mut distances = Vec::with_capacity(self.individuals.len());
+        for indiv in &self.individuals {
+            distances.push(self.representative.distance(&indiv.individual)?);
+        }
+        let (far_index, _) = distances.iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))?;
+        let far_representative = self.individuals[far_index].individual.clone();
+
+        let mut moving = Vec::with_capacity(self.individuals.len());
+        for (indiv, distance) in self.individuals.iter().zip(distances) {
+            moving.push(far_representative.distance(&indiv.individual)? < distance);
+        }
+        if moving.iter().all(|m| *m) || !moving.iter().any(|m| *m) {
+            return None;
+        }
+
+        self.sorted_order.take();
+        let mut moving = moving.into_iter();
+        let (moved, kept): (Vec<_>, Vec<_>) = self.individuals.drain(..)
+            .partition(|_| moving.next().unwrap());
+        self.individuals = kept;
+
+        let mut moved = moved.into_iter().map(|indiv| indiv.individual);
+        let mut new_species = Species::new_descendant(moved.next().unwrap(), new_species_id, self.id);
+        new_species.representative = far_representative;
+        for individual in moved {
+            new_species.insert(individual);
         }
+        Some(new_species)
+    }
+
+    pub fn drain_individuals(&mut self) -> impl Iterator<Item=I> + '_ {
+        self.sorted_order.take();
+        self.individuals.drain(..)
+            .map(|i| {i.individual})
+    }
 
+    fn individual_adjusted_fitness(raw_fitness: F, mut fitness: F, is_best_species: bool, age: &mut Age, last_best_fitness: &mut F, conf: &Conf) -> F {
         // update the best fitness and stagnation counter
-        if fitness >= *last_best_fitness {
-            *last_best_fitness = fitness;
+        if raw_fitness >= *last_best_fitness {
+            *last_best_fitness = raw_fitness;
             age.reset_no_improvements();
         }
 
+        // set small fitness if it is absent
+        if fitness.is_zero() {
+            fitness = F::from(conf.zero_fitness_epsilon).unwrap();
+        }
+
         let number_of_generations = age.generations;
 
-        // boost the fitness up to some young age
-        if number_of_generations < conf.young_age_threshold {
-            fitness = fitness * F::from(conf.young_age_fitness_boost).unwrap();
-        }
+        if let Some(age_fitness_adjustment) = &conf.age_fitness_adjustment {
+            // user defined boosts and penalties
+            fitness = F::from(age_fitness_adjustment(age, fitness.to_f64().unwrap())).unwrap();
+        } else {
+            // boost the fitness up to some young age
+            if number_of_generations < conf.young_age_threshold {
+                fitness = fitness * F::from(conf.young_age_fitness_boost).unwrap();
+            }
 
-        // penalty for old species
-        if number_of_generations > conf.old_age_threshold {
-            fitness = fitness * F::from(conf.old_age_fitness_penalty).unwrap();
+            // penalty for old species
+            if number_of_generations > conf.old_age_threshold {
+                fitness = fitness * F::from(conf.old_age_fitness_penalty).unwrap();
+            }
         }
 
         // Extreme penalty if this species is stagnating for too long time
         // one exception if this is the best species found so far
-        if !is_best_species && age.no_improvements > conf.species_max_stagnation {
+        if conf.stagnation_policy == StagnationPolicy::Penalize
+            && !is_best_species && age.no_improvements > conf.species_max_stagnation {
             fitness = fitness * F::from(0.0000001).unwrap();
         }
 
@@ -247,6 +528,13 @@ impl<'a, I: Individual<F>, F: num::Float> Iterator for SpeciesIter<'a, I,F> {
 
 impl<'a, I: Individual<F>, F: num::Float> ExactSizeIterator for SpeciesIter<'a, I, F> {}
 
+impl<'a, I: Individual<F>, F: num::Float> SpeciesIter<'a, I, F> {
+    /// Iterates through the individuals together with their adjusted fitness (see `Species::adjusted_fitness`)
+    pub fn with_adjusted_fitness(self) -> impl ExactSizeIterator<Item=(&'a I, Option<F>)> {
+        self.inner_iterator.map(|indiv| (&indiv.individual, indiv.adjusted_fitness))
+    }
+}
+
 pub struct SpeciesMutIter<'a, I: Individual<F>, F: num::Float> {
     inner_iterator: IterMut<'a, Indiv<I,F>>
 }
@@ -265,9 +553,12 @@ impl<'a, I: Individual<F>, F: num::Float> Iterator for SpeciesMutIter<'a, I,F> {
 
 pub struct RcSpecies<I: Individual<F>, F: num::Float> {
     pub individuals: Vec<Rc<RefCell<I>>>,
+    representative: I,
     pub id: usize,
+    parent_species_id: Option<usize>,
     age: Age,
     last_best_fitness: F,
+    fitness_history: VecDeque<Option<F>>,
 }
 
 impl<I: Individual<F> + Debug, F: num::Float> RcSpecies<I,F> {
@@ -277,9 +568,13 @@ impl<I: Individual<F> + Debug, F: num::Float> RcSpecies<I,F> {
                 individual: Rc::try_unwrap(indiv).unwrap().into_inner(),
                 adjusted_fitness: None,
             }).collect(),
+            representative: self.representative,
             id: self.id,
+            parent_species_id: self.parent_species_id,
             age: self.age,
             last_best_fitness: self.last_best_fitness,
+            fitness_history: self.fitness_history,
+            sorted_order: OnceCell::new(),
         }
     }
-}
\ No newline at end of file
+}
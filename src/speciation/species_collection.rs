@@ -18,7 +18,10 @@
 use std::cmp::Ordering;
 use std::collections::HashSet;
 
-use crate::speciation::{Individual, Species};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::speciation::{are_compatible, AdjustedFitnessMode, Conf, FitnessSharing, Individual, NegativeFitnessPolicy, SpeciationStrategy, Species, SpeciesIdPolicy};
 use crate::speciation;
 use std::slice::{Iter, IterMut};
 
@@ -57,27 +60,395 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> SpeciesCollection<I, F> {
     /// Removes all empty species (cleanup routine for every case..)
     pub fn cleanup(&mut self) {
         self.collection.retain(|species| !species.is_empty());
+        self.cache_need_updating = true;
+    }
+
+    /// Merges the species whose representatives are compatible. The individuals of the newer species
+    /// (later in the collection) are moved into the older one, which keeps its id and age.
+    ///
+    /// With `conf.species_merge_threshold` set and a distance available, representatives are compatible
+    /// when their distance is within the threshold, otherwise they have to be compatible in both directions.
+    ///
+    /// @param exclude_id_list Species in this list are never merged
+    /// @return the number of species removed by merging
+    pub fn merge_compatible(&mut self, conf: &Conf, exclude_id_list: &HashSet<usize>) -> usize {
+        let mut merged: usize = 0;
+        let mut i = 0;
+        while i < self.collection.len() {
+            let mut j = i + 1;
+            while j < self.collection.len() {
+                if !exclude_id_list.contains(&self.collection[i].id)
+                    && !exclude_id_list.contains(&self.collection[j].id)
+                    && Self::mergeable(&self.collection[i], &self.collection[j], conf) {
+                    let mut removed = self.collection.remove(j);
+                    for individual in removed.drain_individuals() {
+                        self.collection[i].insert(individual);
+                    }
+                    merged += 1;
+                } else {
+                    j += 1;
+                }
+            }
+            i += 1;
+        }
+        if merged > 0 {
+            self.cache_need_updating = true;
+        }
+        merged
+    }
+
+    fn mergeable(a: &Species<I, F>, b: &Species<I, F>, conf: &Conf) -> bool {
+        let (a, b) = match (a.representative(), b.representative()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return false,
+        };
+        if let Some(threshold) = conf.species_merge_threshold {
+            if let Some(distance) = a.distance(b) {
+                return distance <= F::from(threshold).unwrap();
+            }
+        }
+        are_compatible(a, b, conf) && are_compatible(b, a, conf)
+    }
+
+    /// Splits the species holding more than `conf.species_split_fraction` of the population
+    /// (see `Species::split`). The new species get their ids following `conf.species_id_policy`
+    /// (see `allocate_species_id`).
+    ///
+    /// @param exclude_id_list Species in this list are never split
+    /// @return the number of splits performed
+    pub fn split_oversized(&mut self, conf: &Conf, exclude_id_list: &HashSet<usize>, next_species_id: &mut usize) -> usize {
+        let fraction = match conf.species_split_fraction {
+            Some(fraction) => fraction,
+            None => return 0,
+        };
+        let max_size = fraction * self.count_individuals() as f64;
+        let mut used_ids = self.used_species_ids(exclude_id_list);
+        let mut new_species = Vec::new();
+        let species_n = self.collection.len();
+        for species in &mut self.collection {
+            if conf.max_species.is_some_and(|max_species| species_n + new_species.len() >= max_species) {
+                break;
+            }
+            if species.len() as f64 <= max_size || exclude_id_list.contains(&species.id) {
+                continue;
+            }
+            let new_species_id = species_id_candidate(conf.species_id_policy, *next_species_id, &used_ids);
+            if let Some(split) = species.split(new_species_id) {
+                used_ids.insert(new_species_id);
+                *next_species_id = (*next_species_id).max(new_species_id + 1);
+                new_species.push(split);
+            }
+        }
+        let splits = new_species.len();
+        if splits > 0 {
+            self.collection.extend(new_species);
+            self.cache_need_updating = true;
+        }
+        splits
+    }
+
+    /// Chooses the id of a new species following `policy`, `next_species_id` is moved past the chosen id.
+    ///
+    /// @param reserved ids that cannot be used even if no species in the collection has them
+    pub fn allocate_species_id(&self, policy: SpeciesIdPolicy, next_species_id: &mut usize, reserved: &HashSet<usize>) -> usize {
+        let new_species_id = species_id_candidate(policy, *next_species_id, &self.used_species_ids(reserved));
+        *next_species_id = (*next_species_id).max(new_species_id + 1);
+        new_species_id
+    }
+
+    /// Ids of the species in the collection and the `reserved` ones
+    fn used_species_ids(&self, reserved: &HashSet<usize>) -> HashSet<usize> {
+        self.collection.iter()
+            .map(|species| species.id)
+            .chain(reserved.iter().copied())
+            .collect()
+    }
+
+    /// Finds the species `individual` is assigned to, among the ones with a compatible representative:
+    /// the first one, or the nearest one with `SpeciationStrategy::BestMatch` (the first one if the
+    /// distance is not available).
+    ///
+    /// @param exclude_id_list Species in this list will be ignored
+    /// @return the index of the species, None if no species is compatible
+    pub fn find_compatible(&self, individual: &I, conf: &Conf, exclude_id_list: &HashSet<usize>) -> Option<usize> {
+        let mut compatible = self.collection.iter()
+            .enumerate()
+            .filter(|(_, species)| !exclude_id_list.contains(&species.id))
+            .filter(|(_, species)| species.is_compatible(individual, conf));
+        if conf.speciation_strategy != SpeciationStrategy::BestMatch {
+            return compatible.next().map(|(i, _)| i);
+        }
+
+        let (first, first_species) = compatible.next()?;
+        let mut nearest = (first, first_species.representative().and_then(|representative| representative.distance(individual)));
+        for (i, species) in compatible {
+            let distance = species.representative().and_then(|representative| representative.distance(individual));
+            match (distance, nearest.1) {
+                (Some(distance), Some(nearest_distance)) if distance < nearest_distance => nearest = (i, Some(distance)),
+                (Some(_), Some(_)) => {}
+                // no distance available
+                _ => return Some(first),
+            }
+        }
+        Some(nearest.0)
+    }
+
+    /// Finds the species with the representative nearest to `individual`.
+    /// If the distance is not available (see `Individual::distance`), the first species not excluded is returned.
+    ///
+    /// @param exclude_id_list Species in this list will be ignored
+    /// @return the index of the nearest species, None if there are no species left
+    pub fn nearest(&self, individual: &I, exclude_id_list: &HashSet<usize>) -> Option<usize> {
+        self.nearest_by_distance(individual, exclude_id_list)
+            .or_else(|| self.collection.iter().position(|species| !exclude_id_list.contains(&species.id)))
+    }
+
+    /// Like `nearest`, but None if the distance is not available
+    pub fn nearest_by_distance(&self, individual: &I, exclude_id_list: &HashSet<usize>) -> Option<usize> {
+        let mut nearest: Option<(usize, F)> = None;
+        for (i, species) in self.collection.iter().enumerate() {
+            if exclude_id_list.contains(&species.id) {
+                continue;
+            }
+            let distance = species.representative().and_then(|representative| representative.distance(individual))?;
+            if nearest.is_none_or(|(_, nearest_distance)| distance < nearest_distance) {
+                nearest = Some((i, distance));
+            }
+        }
+        nearest.map(|(i, _)| i)
+    }
+
+    /// Removes the species that did not improve for more than `max_stagnation` generations.
+    ///
+    /// @param protected number of best species (by best fitness) never removed, at least the best one is kept
+    /// @param exclude_id_list Species in this list are never removed
+    /// @return the number of removed species
+    pub fn remove_stagnant(&mut self, max_stagnation: usize, protected: usize, exclude_id_list: &HashSet<usize>) -> usize {
+        let mut ranking: Vec<(usize, Option<F>)> = self.collection.iter()
+            .map(|species| (species.id, species.get_best_fitness()))
+            .collect();
+        ranking.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        let protected_ids: HashSet<usize> = ranking.into_iter()
+            .take(protected.max(1))
+            .map(|(id, _)| id)
+            .collect();
+
+        let species_n = self.collection.len();
+        self.collection.retain(|species| {
+            species.age().no_improvements <= max_stagnation
+                || protected_ids.contains(&species.id)
+                || exclude_id_list.contains(&species.id)
+        });
+        let removed = species_n - self.collection.len();
+        if removed > 0 {
+            self.cache_need_updating = true;
+        }
+        removed
+    }
+
+    /// Mean silhouette coefficient of the individuals: how much closer an individual is to the other members of
+    /// its species than to the members of the nearest other species, from -1 (wrong species) to 1 (coherent clusters).
+    /// Individuals alone in their species count as 0.
+    ///
+    /// @param samples number of individuals (randomly chosen) to compute the coefficient for, None to use all of them
+    /// @return the mean coefficient, None with less than two species or if the distance is not available
+    /// (see `Individual::distance`)
+    pub fn silhouette<R: Rng + ?Sized>(&self, samples: Option<usize>, rng: &mut R) -> Option<F> {
+        if self.collection.len() < 2 {
+            return None;
+        }
+        let mut individuals: Vec<(usize, &I)> = self.collection.iter()
+            .enumerate()
+            .flat_map(|(species_i, species)| species.iter().map(move |individual| (species_i, individual)))
+            .collect();
+        if let Some(samples) = samples {
+            if samples < individuals.len() {
+                individuals = individuals.choose_multiple(rng, samples).cloned().collect();
+            }
+        }
+        if individuals.is_empty() {
+            return None;
+        }
+
+        let mut total = F::zero();
+        for &(species_i, individual) in &individuals {
+            let mut own_distance = F::zero();
+            let mut nearest_distance: Option<F> = None;
+            for (other_i, species) in self.collection.iter().enumerate() {
+                let mut sum = F::zero();
+                for other in species.iter() {
+                    sum = sum + individual.distance(other)?;
+                }
+                if other_i == species_i {
+                    if species.len() < 2 {
+                        break;
+                    }
+                    own_distance = sum / F::from(species.len() - 1).unwrap();
+                } else if !species.is_empty() {
+                    let mean_distance = sum / F::from(species.len()).unwrap();
+                    if nearest_distance.is_none_or(|nearest| mean_distance < nearest) {
+                        nearest_distance = Some(mean_distance);
+                    }
+                }
+            }
+            if self.collection[species_i].len() < 2 {
+                continue;
+            }
+            let nearest_distance = nearest_distance?;
+            let max_distance = own_distance.max(nearest_distance);
+            if max_distance > F::zero() {
+                total = total + (nearest_distance - own_distance) / max_distance;
+            }
+        }
+        Some(total / F::from(individuals.len()).unwrap())
+    }
+
+    /// Lowest fitness among the evaluated individuals of all the species
+    pub fn min_fitness(&self) -> Option<F> {
+        self.collection.iter()
+            .flat_map(|species| species.iter())
+            .filter_map(|individual| individual.fitness())
+            .fold(None, |min: Option<F>, fitness| Some(min.map_or(fitness, |min| min.min(fitness))))
     }
 
     /// Deletes all species
     pub fn clear(&mut self) {
-        self.collection.clear()
+        self.collection.clear();
+        self.cache_need_updating = true;
+    }
+
+    /// Returns the species at the given index
+    pub fn get(&self, index: usize) -> &Species<I, F> { &self.collection[index] }
+
+    /// Returns the species at the given index, mutable
+    pub fn get_mut(&mut self, index: usize) -> &mut Species<I, F> {
+        self.cache_need_updating = true;
+        &mut self.collection[index]
     }
 
     /// Iterates through the species
-    pub fn iter(&self) -> Iter<Species<I, F>> { self.collection.iter() }
+    pub fn iter(&self) -> Iter<'_, Species<I, F>> { self.collection.iter() }
 
     /// Iterates through the (mutable) species
-    pub fn iter_mut(&mut self) -> IterMut<Species<I, F>> { self.collection.iter_mut() }
+    pub fn iter_mut(&mut self) -> IterMut<'_, Species<I, F>> { self.collection.iter_mut() }
+
+    /// Iterates through the (mutable) individuals of all species.
+    /// Fitness values may change, so the cache is invalidated.
+    pub fn individuals_mut(&mut self) -> impl Iterator<Item=&mut I> + '_ {
+        self.cache_need_updating = true;
+        self.collection.iter_mut().flat_map(|species| species.iter_mut())
+    }
 
     /// Computes the adjusted fitness for all species
-    pub fn compute_adjust_fitness(&mut self, conf: &speciation::Conf)
+    ///
+    /// @param generation current generation, for the temperature of `AdjustedFitnessMode::Boltzmann`
+    /// @return the ids of the species skipped because no individual is evaluated (see `UnevaluatedSpeciesPolicy::Skip`)
+    pub fn compute_adjust_fitness(&mut self, conf: &speciation::Conf, generation: usize) -> Vec<usize>
     {
-        let best = self.best.expect("best should be present");
-        let best_id = self.collection[best].id;
-        for species in &mut self.collection {
-            species.compute_adjust_fitness(species.id == best_id, conf);
+        // no best species if nothing was evaluated
+        let best_id = self.best.map(|best| self.collection[best].id);
+        let shared_value: Box<dyn Fn(F) -> F> = match (conf.adjusted_fitness_mode, conf.negative_fitness_policy) {
+            (AdjustedFitnessMode::Rank, _) => {
+                let mut fitnesses: Vec<F> = self.collection.iter()
+                    .flat_map(|species| species.iter())
+                    .map(|individual| individual.fitness().unwrap_or(F::zero()))
+                    .collect();
+                fitnesses.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                fitnesses.dedup();
+                // rank of the fitness, starting from 1
+                Box::new(move |fitness| {
+                    let rank = fitnesses.partition_point(|other| *other < fitness) + 1;
+                    F::from(rank).unwrap()
+                })
+            }
+            (AdjustedFitnessMode::Boltzmann { .. }, _) => {
+                let temperature = F::from(conf.adjusted_fitness_mode.temperature(generation).unwrap()).unwrap();
+                // normalizing by the best fitness avoids overflows, the shares do not change
+                let max_fitness = self.collection.iter()
+                    .filter_map(|species| species.max_fitness())
+                    .reduce(F::max)
+                    .unwrap_or(F::zero());
+                Box::new(move |fitness| ((fitness - max_fitness) / temperature).exp())
+            }
+            (AdjustedFitnessMode::Raw, NegativeFitnessPolicy::Reject) => Box::new(|fitness| fitness),
+            (AdjustedFitnessMode::Raw, NegativeFitnessPolicy::ShiftByMinimum) => {
+                let fitness_offset = self.min_fitness().unwrap_or(F::zero()).min(F::zero());
+                Box::new(move |fitness| fitness - fitness_offset)
+            }
+        };
+        let niche_counts: Option<Vec<Vec<F>>> = match conf.fitness_sharing {
+            FitnessSharing::SpeciesSize => None,
+            FitnessSharing::Kernel { sigma, alpha } => Some(self.niche_counts(sigma, alpha)
+                .expect("FitnessSharing::Kernel requires Individual::distance")),
+        };
+        let mut skipped_species = Vec::new();
+        for (i, species) in self.collection.iter_mut().enumerate() {
+            let species_niche_counts = niche_counts.as_ref().map(|niche_counts| niche_counts[i].as_slice());
+            if species.compute_adjust_fitness(Some(species.id) == best_id, shared_value.as_ref(), species_niche_counts, conf) {
+                skipped_species.push(species.id);
+            }
+        }
+
+        // Novelty bonus for the species far from all the others
+        if conf.species_novelty_bonus != 1.0 {
+            let bonus = F::from(conf.species_novelty_bonus).unwrap();
+            for i in self.novel_species(F::from(conf.species_novelty_distance).unwrap()) {
+                self.collection[i].scale_adjusted_fitness(bonus);
+            }
+        }
+
+        skipped_species
+    }
+
+    /// Niche count of every individual (grouped like the species) for explicit fitness sharing,
+    /// see `FitnessSharing::Kernel`
+    ///
+    /// @return None if `Individual::distance` is not available
+    fn niche_counts(&self, sigma: f64, alpha: f64) -> Option<Vec<Vec<F>>> {
+        let population: Vec<&I> = self.collection.iter()
+            .flat_map(|species| species.iter())
+            .collect();
+        let mut niche_counts = Vec::with_capacity(self.collection.len());
+        for species in &self.collection {
+            let mut species_niche_counts = Vec::with_capacity(species.len());
+            for individual in species.iter() {
+                let mut niche_count = 0.0;
+                for other in &population {
+                    let distance = individual.distance(other)?.to_f64().unwrap();
+                    if distance < sigma {
+                        niche_count += 1.0 - (distance / sigma).powf(alpha);
+                    }
+                }
+                species_niche_counts.push(F::from(niche_count).unwrap());
+            }
+            niche_counts.push(species_niche_counts);
         }
+        Some(niche_counts)
+    }
+
+    /// Finds the species whose representative is farther than `min_distance` from all the other representatives.
+    /// Species without a representative or a distance (see `Individual::distance`) are never novel.
+    ///
+    /// @return the indices of the novel species
+    fn novel_species(&self, min_distance: F) -> Vec<usize> {
+        if self.collection.len() < 2 {
+            return Vec::new();
+        }
+        self.collection.iter()
+            .enumerate()
+            .filter(|(i, species)| {
+                let representative = match species.representative() {
+                    Some(representative) => representative,
+                    None => return false,
+                };
+                self.collection.iter()
+                    .enumerate()
+                    .filter(|(j, _)| j != i)
+                    .filter_map(|(_, other)| other.representative())
+                    .all(|other| representative.distance(other).is_some_and(|d| d > min_distance))
+            })
+            .map(|(i, _)| i)
+            .collect()
     }
 
     /// Updates the best_species, increases age for all species
@@ -110,14 +481,51 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> SpeciesCollection<I, F> {
         self.best
     }
 
-    /// Finds the worst species (based on the best fitness of that species)
-    /// Crashes if there are no species with at least `minimal_size` individuals
+    /// Returns the index pointing to the best species without updating the cache:
+    /// the cached value is used when valid, otherwise it is recalculated.
+    pub fn best(&self) -> Option<usize> {
+        if self.cache_need_updating {
+            self.find_best()
+        } else {
+            self.best
+        }
+    }
+
+    fn find_best(&self) -> Option<usize> {
+        self.collection.iter()
+            .enumerate()
+            .filter_map(|(i, species)| {
+                // if best_fitness is None, this species will be filtered out
+                species.get_best_fitness().map(|f| (i, f))
+            })
+            .max_by(|(_, fitness_a), (_, fitness_b)| if fitness_a > fitness_b { Ordering::Greater } else { Ordering::Less })
+            .map(|(i, _)| i)
+    }
+
+    /// Finds the best species ignoring the species in `exclude_id_list` (not cached)
     ///
-    /// This function is not const because it returns a modifiable iterator.
+    /// @param exclude_id_list Species in this list will be ignored
+    /// @return the index pointing to the best species
+    pub fn get_best_excluding(&self, exclude_id_list: &HashSet<usize>) -> Option<usize> {
+        self.collection.iter()
+            .enumerate()
+            .filter(|(_, species)| !exclude_id_list.contains(&species.id))
+            .filter_map(|(i, species)| {
+                // if best_fitness is None, this species will be filtered out
+                species.get_best_fitness().map(|f| (i, f))
+            })
+            .max_by(|(_, fitness_a), (_, fitness_b)| if fitness_a > fitness_b { Ordering::Greater } else { Ordering::Less })
+            .map(|(i, _)| i)
+    }
+
+    /// Finds the worst species (based on the best fitness of that species)
+    /// Species without an evaluated individual are not considered.
+    /// When multiple species have the same best fitness, the first one is returned.
     ///
     /// @param minimal_size Species with less individuals than this will not be considered
     /// @param exclude_id_list Species in this list will be ignored
-    /// @return the iterator pointing to the worst species
+    /// @return the index and a reference to the worst species,
+    /// None if no species is left after the size and id filters
     pub fn get_worst(&self, minimal_size: usize, exclude_id_list: Option<&HashSet<usize>>) -> Option<(usize, &Species<I,F>)> {
         assert!(!self.collection.is_empty());
 
@@ -164,18 +572,18 @@ impl<I: Individual<F>, F: num::Float + std::iter::Sum> SpeciesCollection<I, F> {
         assert!(!self.collection.is_empty());
 
         // Best
-        self.best = self.collection.iter()
-            .enumerate()
-            .filter_map(|(i, species)| {
-                // if best_fitness is None, this species will be filtered out
-                species.get_best_fitness().map(|f| (i, f))
-            })
-            .max_by(|(_, fitness_a), (_, fitness_b)| if fitness_a > fitness_b { Ordering::Greater } else { Ordering::Less })
-            .map(|(i, _)| i);
+        self.best = self.find_best();
 
         // Cannot calculate WORST cache, because there are 2 different
         // version of the worst individual. Which one should be cached?
 
         self.cache_need_updating = false;
     }
-}
\ No newline at end of file
+}
+/// Id for a new species: `next_species_id`, or the smallest id (from 1) not in `used_ids` with `SpeciesIdPolicy::ReuseExtinct`
+fn species_id_candidate(policy: SpeciesIdPolicy, next_species_id: usize, used_ids: &HashSet<usize>) -> usize {
+    match policy {
+        SpeciesIdPolicy::Monotonic => next_species_id,
+        SpeciesIdPolicy::ReuseExtinct => (1..).find(|id| !used_ids.contains(id)).unwrap(),
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::analysis::{classical_mds, k_medoids, write_embedding_csv, write_embedding_json, EmbeddingPoint};
+
+#[test]
+fn mds_preserves_distances() {
+    // the corners of a 3x4 rectangle
+    let points = [(0.0, 0.0), (3.0, 0.0), (3.0, 4.0), (0.0, 4.0)];
+    let distances: Vec<Vec<f64>> = points.iter()
+        .map(|(ax, ay): &(f64, f64)| points.iter()
+            .map(|(bx, by)| ((ax - bx).powi(2) + (ay - by).powi(2)).sqrt())
+            .collect())
+        .collect();
+
+    let embedding = classical_mds(&distances);
+    for (i, a) in embedding.iter().enumerate() {
+        for (j, b) in embedding.iter().enumerate() {
+            let distance = ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+            assert!((distance - distances[i][j]).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn k_medoids_clusters() {
+    let points = [0.0, 1.0, 2.0, 10.0, 11.0, 30.0];
+    let distances: Vec<Vec<f64>> = points.iter()
+        .map(|a: &f64| points.iter().map(|b| (a - b).abs()).collect())
+        .collect();
+
+    let (medoids, clusters) = k_medoids(&distances, 3, 10);
+    assert_eq!(medoids.len(), 3);
+    assert_eq!(clusters[0], clusters[1]);
+    assert_eq!(clusters[1], clusters[2]);
+    assert_eq!(clusters[3], clusters[4]);
+    assert_ne!(clusters[2], clusters[3]);
+    assert_ne!(clusters[4], clusters[5]);
+    assert_eq!(medoids[clusters[0]], 1);
+
+    // no more clusters than distinct elements
+    let (medoids, _) = k_medoids(&[vec![0.0, 0.0], vec![0.0, 0.0]], 2, 10);
+    assert_eq!(medoids.len(), 1);
+}
+
+#[test]
+fn csv_export() {
+    let mut output = Vec::new();
+    write_embedding_csv(&[EmbeddingPoint { species_id: 2, x: 0.5, y: -1.0 }], &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "species_id,x,y\n2,0.5,-1\n");
+}
+
+#[test]
+fn json_export() {
+    let points = [
+        EmbeddingPoint { species_id: 2, x: 0.5, y: -1.0 },
+        EmbeddingPoint { species_id: 3, x: f64::NAN, y: f64::INFINITY },
+    ];
+    let mut output = Vec::new();
+    write_embedding_json(&points, &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(),
+               "[{\"species_id\":2,\"x\":0.5,\"y\":-1},{\"species_id\":3,\"x\":null,\"y\":null}]\n");
+}
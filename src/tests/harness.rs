@@ -0,0 +1,125 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::env;
+use std::fs::{self, File};
+use std::io::BufReader;
+
+use crate::harness::{Checkpointing, ExampleRunner, Preset, Report, Termination};
+use crate::population::checkpoint::Checkpoint;
+use crate::speciation::{Conf, FitnessSharing, Individual, MassExtinction};
+use crate::tests::IndividualTest;
+
+#[test]
+fn manifest_records_the_run() {
+    let conf = Conf {
+        fitness_sharing: FitnessSharing::Kernel { sigma: 2.0, alpha: 1.0 },
+        mass_extinction: Some(MassExtinction { stagnation: 20, surviving_species: 2 }),
+        ..Conf::default()
+    };
+    let runner = ExampleRunner::new(conf, u64::MAX, 10, Some(1.5f32));
+    let manifest: serde_json::Value = serde_json::from_str(&runner.manifest::<IndividualTest>()).unwrap();
+
+    assert_eq!(manifest["crate"], "rustneat");
+    assert!(manifest["genome"].as_str().unwrap().ends_with("IndividualTest"));
+    assert_eq!(manifest["seed"].as_u64(), Some(u64::MAX));
+    assert_eq!(manifest["max_generations"], 10);
+    assert_eq!(manifest["target_fitness"].as_f64(), Some(1.5));
+    assert_eq!(manifest["strategies"]["selection"], "uniform_random_in_species");
+    assert_eq!(manifest["strategies"]["offspring_allocation"], "floor_best_species");
+    assert_eq!(manifest["strategies"]["fitness_sharing"], "kernel");
+
+    // one field per parameter
+    let conf = manifest["conf"].as_object().unwrap();
+    assert_eq!(conf.len(), 42);
+    assert_eq!(conf["total_population_size"], 100);
+    assert_eq!(conf["crossover_probability"].as_f64(), Some(1.0));
+    assert_eq!(conf["compatibility_threshold"], serde_json::Value::Null);
+    assert_eq!(conf["preserve_champion"], false);
+    assert_eq!(conf["speciation_strategy"], "first_match");
+    assert_eq!(conf["fitness_sharing"]["kind"], "kernel");
+    assert_eq!(conf["fitness_sharing"]["sigma"].as_f64(), Some(2.0));
+    assert_eq!(conf["mass_extinction"]["surviving_species"], 2);
+    assert_eq!(conf["trial_budget"]["kind"], "fixed");
+    assert_eq!(conf["trial_budget"]["trials"], 1);
+}
+
+#[test]
+fn example_runner_test() {
+    let conf = Conf {
+        total_population_size: 8,
+        ..Conf::default()
+    };
+    let reports = Cell::new(0);
+    let manifest = RefCell::new(None);
+    let runner = ExampleRunner::new(conf.clone(), 3, 5, None);
+    let outcome = runner.run(|individual: &mut IndividualTest| individual.evaluate(), |report| match report {
+        Report::Start { manifest: run_manifest } => {
+            // before any generation
+            assert_eq!(reports.get(), 0);
+            *manifest.borrow_mut() = Some(run_manifest.to_string());
+        }
+        Report::Generation(_genus) => reports.set(reports.get() + 1),
+    }).unwrap();
+    assert_eq!(manifest.into_inner(), Some(runner.manifest::<IndividualTest>()));
+    assert_eq!(outcome.termination, Termination::MaxGenerations);
+    assert_eq!(outcome.genus.generation(), 5);
+    assert_eq!(outcome.genus.count_individuals(), 8);
+    // once per generation and once for the last genus
+    assert_eq!(reports.get(), 6);
+    assert!(outcome.genus.iter_individuals().all(|individual| individual.fitness().unwrap() <= outcome.best_fitness));
+
+    // any individual reaches the target
+    let runner = ExampleRunner::new(conf, 3, 5, Some(0.0));
+    let outcome = runner.run(|individual: &mut IndividualTest| individual.evaluate(), |_report| {}).unwrap();
+    assert_eq!(outcome.termination, Termination::TargetFitness);
+    assert_eq!(outcome.genus.generation(), 0);
+}
+
+#[test]
+fn presets_test() {
+    for preset in [Preset::Neat, Preset::MutationOnly, Preset::Diverse] {
+        let conf = preset.conf(20);
+        assert_eq!(conf.total_population_size, 20);
+        assert!(conf.validate().is_ok());
+    }
+    let runner: ExampleRunner<f32> = ExampleRunner::with_preset(Preset::MutationOnly, 8, 3, 5, None);
+    assert_eq!(runner.conf.crossover_probability, 0.0);
+}
+
+#[test]
+fn checkpointing_test() {
+    let path = env::temp_dir().join(format!("rustneat-checkpointing-test-{}", std::process::id()));
+    let mut runner = ExampleRunner::with_preset(Preset::Neat, 8, 3, 4, None);
+    runner.checkpointing = Some(Checkpointing { path: path.clone(), interval: 3 });
+    let outcome = runner.run(|individual: &mut IndividualTest| individual.evaluate(), |_report| {}).unwrap();
+
+    // the last checkpoint is the one of the end of the run
+    let checkpoint = Checkpoint::read(BufReader::new(File::open(&path).unwrap())).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(checkpoint.generation, 4);
+    assert_eq!(checkpoint.manifest, runner.manifest::<IndividualTest>());
+    assert_eq!(checkpoint.individuals.len(), outcome.genus.count_individuals());
+
+    runner.checkpointing = None;
+    runner.max_generations = 6;
+    let resumed = runner.resume(&checkpoint, |individual: &mut IndividualTest| individual.evaluate(), |_report| {}).unwrap();
+    assert_eq!(resumed.termination, Termination::MaxGenerations);
+    assert_eq!(resumed.genus.generation(), 6);
+    assert_eq!(resumed.genus.count_individuals(), 8);
+}
@@ -15,18 +15,34 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::io::Read;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
 use std::ptr;
+use std::time::Duration;
 
 use rand::prelude::*;
 
-use crate::speciation::{Conf, Genus, Individual};
+use crate::activations::Activation;
+use crate::genome::{genome_test_suite, Genome};
+use crate::population::checkpoint::Checkpoint;
+use crate::population::{bootstrap, Population};
+use crate::selection::{AdjustedFitnessProportional, Selector, StochasticUniversalSampling, Tournament};
+use crate::speciation::{are_crossover_compatible, cost_to_fitness, AdjustedFitnessMode, Conf, FitnessSharing, Genus, GenusSeed, Hypermutation, Individual, MassExtinction, Minimize, MutationSchedule, NegativeFitnessPolicy, OffspringRounding, OperatorError, OrphanPolicy, RepresentativePolicy, ReproductionOperators, SpeciationStrategy, SpeciesIdPolicy, SpeciesIter, TrialBudget, UnevaluatedSpeciesPolicy};
+
+mod analysis;
+mod harness;
+mod selection;
+mod self_adaptation;
+mod species;
+mod species_collection;
 
 #[derive(Clone, Debug)]
-struct IndividualTest {
-    id: usize,
-    genome: Vec<bool>,
-    fitness: Option<f32>,
+pub(crate) struct IndividualTest {
+    pub(crate) id: usize,
+    pub(crate) genome: Vec<bool>,
+    pub(crate) fitness: Option<f32>,
 }
 
 impl IndividualTest {
@@ -37,10 +53,26 @@ impl IndividualTest {
             fitness: None,
         }
     }
+    /// Individual with a single gene and the given fitness
+    pub fn with_fitness(id: usize, fitness: Option<f32>) -> Self {
+        Self {
+            id,
+            genome: vec![false],
+            fitness,
+        }
+    }
+    /// Individual whose first `set_genes` genes (out of `size`) are set
+    pub fn with_set_genes(id: usize, size: usize, set_genes: usize) -> Self {
+        Self {
+            id,
+            genome: (0..size).map(|gene| gene < set_genes).collect(),
+            fitness: None,
+        }
+    }
     pub fn random(id: usize, size: usize, rng: &mut ThreadRng) -> Self {
         Self {
             id,
-            genome: (0..size).into_iter().map(|_| rng.gen()).collect(),
+            genome: (0..size).map(|_| rng.gen()).collect(),
             fitness: None,
         }
     }
@@ -89,38 +121,143 @@ impl Individual<f32> for IndividualTest {
             self.genome.iter().zip(other.genome.iter())
                 .map(|(s, o)| if s == o { 0 } else { 1 })
                 .sum();
-        distance > (self.genome.len() / 3)
+        distance <= (self.genome.len() / 3)
+    }
+
+    fn distance(&self, other: &Self) -> Option<f32> {
+        assert_eq!(self.genome.len(), other.genome.len());
+        let distance = self.genome.iter().zip(other.genome.iter())
+            .filter(|(s, o)| s != o)
+            .count();
+        Some(distance as f32)
+    }
+
+    fn genome_hash(&self) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.genome.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+}
+
+impl Genome<f32> for IndividualTest {
+    fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            id: 0,
+            genome: (0..10).map(|_| rng.gen()).collect(),
+            fitness: None,
+        }
+    }
+
+    fn reproduce<R: Rng + ?Sized>(&self, _rng: &mut R) -> Self {
+        Self {
+            fitness: None,
+            ..self.clone()
+        }
+    }
+
+    fn crossover<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        let swap_point = rng.gen_range(0..self.genome.len());
+        Self {
+            id: 0,
+            genome: self.genome.iter()
+                .take(swap_point)
+                .chain(other.genome.iter().skip(swap_point))
+                .cloned()
+                .collect(),
+            fitness: None,
+        }
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let pos = rng.gen_range(0..self.genome.len());
+        self.genome[pos] = !self.genome[pos];
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        Some(self.genome.iter().map(|g| *g as u8).collect())
+    }
+
+    fn deserialize(bytes: &[u8]) -> Option<Self> {
+        Some(Self {
+            id: 0,
+            genome: bytes.iter().map(|b| *b != 0).collect(),
+            fitness: None,
+        })
     }
 }
 
+/// Keeps the best individuals among the new and old ones
+fn elitist_population_management(new_individuals: Vec<IndividualTest>,
+                                 old_individuals: Vec<IndividualTest>,
+                                 target_population: usize) -> Vec<IndividualTest> {
+    assert!(new_individuals.len() + old_individuals.len() >= target_population);
+    let mut population: Vec<IndividualTest> = new_individuals.into_iter()
+        .chain(old_individuals)
+        .collect();
+    population.sort_by(|a, b| b.fitness().partial_cmp(&a.fitness()).unwrap());
+    population.truncate(target_population);
+    population
+}
+
+/// Runs a full generation with simple asexual operators, generating the new individuals with
+/// `generation_conf` and creating the next generation with `next_generation_conf`
+fn run_generation(genus: &mut Genus<IndividualTest, f32>,
+                  generation_conf: &Conf,
+                  next_generation_conf: &Conf,
+                  id_counter: &Cell<usize>) -> Genus<IndividualTest, f32> {
+    let mut rng = rand::thread_rng();
+    let mut generated_individuals = genus.update(generation_conf)
+        .generate_new_individuals(
+            generation_conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| {
+                let mut child = parent.clone();
+                child.id = id_counter.get();
+                id_counter.set(id_counter.get() + 1);
+                child
+            },
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| {
+                let mut child = parent1.clone();
+                child.id = id_counter.get();
+                id_counter.set(id_counter.get() + 1);
+                child
+            },
+            &mut |individual: &mut IndividualTest| individual.mutate(&mut rng),
+        );
+    generated_individuals.evaluate(|individual| individual.evaluate());
+    genus.next_generation(next_generation_conf, generated_individuals, elitist_population_management)
+}
+
 #[test]
 fn evolution_test() {
     const POPULATION_SIZE: usize = 10;
     const GENOME_SIZE: usize = 10;
     const MAX_GENERATIONS: usize = 100;
-    let mut rng = rand::thread_rng();
+    let rng = RefCell::new(rand::thread_rng());
 
     let mut genus: Genus<IndividualTest, f32> = crate::speciation::Genus::new();
-    let initial_population: Vec<IndividualTest> = (0..POPULATION_SIZE).into_iter()
-        .map(|i| IndividualTest::random(i, GENOME_SIZE, &mut rng))
-        .collect();
+    let initial_population: Vec<IndividualTest> = bootstrap::from_factory(POPULATION_SIZE, |i| {
+        IndividualTest::random(i, GENOME_SIZE, &mut rng.borrow_mut())
+    });
 
-    let mut id_counter = initial_population.len();
-
-    genus.speciate(initial_population.into_iter());
-    assert_eq!(genus.count_individuals(), POPULATION_SIZE);
+    let id_counter = Cell::new(initial_population.len());
 
     let conf = Conf {
         total_population_size: POPULATION_SIZE,
-        crossover: true,
+        crossover_probability: 1.0,
         young_age_threshold: 2,
         old_age_threshold: 10,
         species_max_stagnation: 20,
         young_age_fitness_boost: 1.1,
         old_age_fitness_penalty: 0.9,
+        ..Conf::default()
     };
 
-    let mut best_fitness = f32::NEG_INFINITY;
+    genus.speciate(&conf, initial_population.into_iter());
+    assert_eq!(genus.count_individuals(), POPULATION_SIZE);
+
+    let best_fitness = Cell::new(f32::NEG_INFINITY);
 
 
     // LAMBDA FUNCTIONS FOR GENOTYPE OPERATIONS
@@ -130,66 +267,2021 @@ fn evolution_test() {
 
     let mut crossover_1 = |parent: &IndividualTest| {
         let mut child = parent.clone();
-        child.id = id_counter;
-        id_counter +=1;
+        child.id = id_counter.get();
+        id_counter.set(id_counter.get() + 1);
         child
     };
 
     let mut crossover_2 = |parent1: &IndividualTest, parent2: &IndividualTest| {
-        let child = parent1.crossover(parent2, id_counter, &mut rng);
-        id_counter +=1;
+        let child = parent1.crossover(parent2, id_counter.get(), &mut rng.borrow_mut());
+        id_counter.set(id_counter.get() + 1);
         child
     };
 
     let mut mutate = |individual: &mut IndividualTest| {
-        individual.mutate(&mut rng)
+        individual.mutate(&mut rng.borrow_mut())
     };
 
-    let population_manager = |new_individuals: Vec<IndividualTest>, old_individuals: Vec<IndividualTest>, target_population: usize| {
-        assert!(new_individuals.len() + old_individuals.len() > target_population);
-        new_individuals.into_iter()
-            .chain(old_individuals.into_iter())
-            .take(target_population)
-            .collect()
-    };
-
-    let evaluate = |new_individual: &mut IndividualTest| {
+    let mut evaluate = |new_individual: &mut IndividualTest| {
         let fitness = new_individual.evaluate();
-        if fitness > best_fitness {
-            best_fitness = fitness;
+        if fitness > best_fitness.get() {
+            best_fitness.set(fitness);
         }
         fitness
     };
 
     // EVOLUTION START
 
-    let mut generation_n: usize = 0;
-
-    genus.ensure_evaluated_population(evaluate);
+    genus.ensure_evaluated_population(&mut evaluate);
 
-    while best_fitness < GENOME_SIZE as f32 {
-        generation_n += 1;
-        println!("Starting generation {}", generation_n);
+    while best_fitness.get() < GENOME_SIZE as f32 {
+        println!("Starting generation {}", genus.generation() + 1);
         let mut generated_individuals = genus.update(&conf)
             .generate_new_individuals(
                 &conf,
-                &mut |mut it| it.next().unwrap(),
+                &mut |it| it.max_by(|a, b| a.fitness().partial_cmp(&b.fitness()).unwrap()).unwrap(),
                 &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
                 &mut crossover_1,
                 &mut crossover_2,
                 &mut mutate,
             );
 
-        generated_individuals.evaluate(evaluate);
+        generated_individuals.evaluate(&mut evaluate);
 
         genus = genus.next_generation(&conf,
                                       generated_individuals,
-                                      population_manager);
+                                      elitist_population_management);
+
+        assert!(genus.generation() <= MAX_GENERATIONS, "Evolution did not converge in {} generations", MAX_GENERATIONS);
+    }
+
+    println!("Evolution took {} generations to complete with a fitness of {}", genus.generation(), best_fitness.get());
+}
+
+#[test]
+fn species_lockout_test() {
+    const POPULATION_SIZE: usize = 10;
+    const GENOME_SIZE: usize = 10;
+    let rng = RefCell::new(rand::thread_rng());
+
+    // Two clearly separated niches: all-false genomes (species 1) and all-true genomes (species 2)
+    let initial_population: Vec<IndividualTest> = (0..POPULATION_SIZE)
+        .map(|i| {
+            let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+            if i % 2 == 1 {
+                individual.genome = vec![true; GENOME_SIZE];
+            }
+            individual
+        })
+        .collect();
+    let id_counter = Cell::new(initial_population.len());
+
+    let conf = Conf {
+        total_population_size: POPULATION_SIZE,
+        ..Conf::default()
+    };
+
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(&conf, initial_population.into_iter());
+    assert_eq!(genus.species_count(), 2);
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+
+    assert!(genus.lock_species(2));
+    assert!(!genus.lock_species(2));
+    assert!(genus.is_species_locked(2));
+    let is_locked_individual = |individual: &IndividualTest| individual.genome.iter().all(|g| *g);
+
+    let mut generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| {
+                assert!(!is_locked_individual(parent));
+                let mut child = parent.clone();
+                child.id = id_counter.get();
+                id_counter.set(id_counter.get() + 1);
+                child
+            },
+            &mut |parent1: &IndividualTest, parent2: &IndividualTest| {
+                assert!(!is_locked_individual(parent1) && !is_locked_individual(parent2));
+                let child = parent1.crossover(parent2, id_counter.get(), &mut rng.borrow_mut());
+                id_counter.set(id_counter.get() + 1);
+                child
+            },
+            &mut |individual: &mut IndividualTest| individual.mutate(&mut rng.borrow_mut()),
+        );
+
+    // only the unlocked half of the population is regenerated
+    assert_eq!(generated_individuals.need_evaluation.len(), POPULATION_SIZE / 2);
+    generated_individuals.evaluate(|individual| individual.evaluate());
+
+    let next_genus = genus.next_generation(&conf, generated_individuals, |new_individuals, _old_individuals, target| {
+        assert!(new_individuals.iter().all(|individual| !is_locked_individual(individual)));
+        new_individuals.into_iter().take(target).collect()
+    });
+
+    assert_eq!(next_genus.count_individuals(), POPULATION_SIZE);
+    assert!(next_genus.is_species_locked(2));
+}
+
+#[test]
+fn population_scaling_test() {
+    const GENOME_SIZE: usize = 10;
+    let mut rng = rand::thread_rng();
+    let conf = |total_population_size| Conf {
+        total_population_size,
+        ..Conf::default()
+    };
+
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(&conf(10), (0..10).map(|i| IndividualTest::random(i, GENOME_SIZE, &mut rng)));
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    let id_counter = Cell::new(10);
+
+    // size changed between the generation and the next_generation call
+    let mut genus = run_generation(&mut genus, &conf(10), &conf(14), &id_counter);
+    assert_eq!(genus.count_individuals(), 14);
+    assert_eq!(genus.generation(), 1);
+    let mut genus = run_generation(&mut genus, &conf(14), &conf(6), &id_counter);
+    assert_eq!(genus.count_individuals(), 6);
+
+    // size changed before generating the new individuals
+    let genus = run_generation(&mut genus, &conf(9), &conf(9), &id_counter);
+    assert_eq!(genus.count_individuals(), 9);
+}
+
+#[test]
+fn bootstrap_spread_test() {
+    const GENOME_SIZE: usize = 10;
+    let mut rng = rand::thread_rng();
+    let hamming = |a: &IndividualTest, b: &IndividualTest| {
+        a.genome.iter().zip(b.genome.iter()).filter(|(x, y)| x != y).count()
+    };
+
+    let population = bootstrap::from_factory_spread(
+        8, 2, 1000,
+        |id| IndividualTest::random(id, GENOME_SIZE, &mut rng),
+        hamming);
+
+    assert_eq!(population.iter().map(|i| i.id).collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+    for (i, a) in population.iter().enumerate() {
+        for b in population.iter().skip(i + 1) {
+            assert!(hamming(a, b) >= 2);
+        }
+    }
+}
+
+#[test]
+fn compatibility_threshold_test() {
+    const GENOME_SIZE: usize = 10;
+    let population = || {
+        let mut different = IndividualTest::empty(2, GENOME_SIZE);
+        different.genome[0] = true;
+        vec![IndividualTest::empty(0, GENOME_SIZE), IndividualTest::empty(1, GENOME_SIZE), different].into_iter()
+    };
+
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(&Conf::default(), population());
+    assert_eq!(genus.species_count(), 1);
+
+    let conf = Conf {
+        compatibility_threshold: Some(0.5),
+        ..Conf::default()
+    };
+    genus.speciate(&conf, population());
+    assert_eq!(genus.species_count(), 2);
+}
+
+#[test]
+fn respeciate_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf::default();
+    let all_true = |id| {
+        let mut individual = IndividualTest::empty(id, GENOME_SIZE);
+        individual.genome = vec![true; GENOME_SIZE];
+        individual
+    };
+
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(&conf, vec![IndividualTest::empty(0, GENOME_SIZE), all_true(1)].into_iter());
+    assert_eq!(genus.species_count(), 2);
+
+    // the all-false species is not populated anymore, the all-true one is kept
+    // and a new one is created for the half-true individual
+    let mut half_true = IndividualTest::empty(4, GENOME_SIZE);
+    half_true.genome[..GENOME_SIZE / 2].iter_mut().for_each(|g| *g = true);
+    genus.respeciate(&conf, vec![all_true(2), all_true(3), half_true].into_iter());
+    assert_eq!(genus.species_count(), 2);
+    assert_eq!(genus.count_individuals(), 3);
+}
+
+#[test]
+fn genome_test_suite_test() {
+    genome_test_suite::run::<IndividualTest, f32>();
+    genome_test_suite::run::<Minimize<IndividualTest>, f32>();
+}
+
+/// Runs a generation with asexual reproduction and the given mutation, the population management
+/// keeps only the new individuals
+fn replace_generation<M: FnMut(&mut IndividualTest)>(genus: &mut Genus<IndividualTest, f32>,
+                                                     conf: &Conf,
+                                                     mut mutate: M) -> Genus<IndividualTest, f32> {
+    let mut generated_individuals = genus.update(conf)
+        .generate_new_individuals(
+            conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut mutate,
+        );
+    generated_individuals.evaluate(|individual| individual.evaluate());
+    genus.next_generation(conf, generated_individuals, |mut new_individuals, _old_individuals, target_population| {
+        new_individuals.truncate(target_population);
+        new_individuals
+    })
+}
+
+/// Collects the fitness of the individuals offered to the selection (requires `conf.crossover_probability == 0`)
+fn selectable_fitnesses(genus: &mut Genus<IndividualTest, f32>, conf: &Conf) -> Vec<f32> {
+    let fitnesses = RefCell::new(Vec::new());
+    genus.update(conf)
+        .generate_new_individuals(
+            conf,
+            &mut |it| {
+                let individuals: Vec<&IndividualTest> = it.collect();
+                fitnesses.borrow_mut().extend(individuals.iter().map(|individual| individual.fitness().unwrap()));
+                individuals[0]
+            },
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    fitnesses.into_inner()
+}
+
+/// Genus with `size` individuals with all the genes set
+fn full_genus(size: usize, genome_size: usize, conf: &Conf) -> Genus<IndividualTest, f32> {
+    let mut genus = genus_with_genomes(size, conf, |_| vec![true; genome_size]);
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    genus
+}
+
+/// Genus with `size` unevaluated individuals, individual `i` has the genome `genome(i)`
+fn genus_with_genomes<G: FnMut(usize) -> Vec<bool>>(size: usize, conf: &Conf, mut genome: G) -> Genus<IndividualTest, f32> {
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(conf, (0..size).map(|i| IndividualTest { id: i, genome: genome(i), fitness: None }));
+    genus
+}
 
-        if generation_n > MAX_GENERATIONS {
-            assert!(false);
+/// Configuration for `size` individuals generated by asexual reproduction only
+fn asexual_conf(size: usize) -> Conf {
+    Conf {
+        total_population_size: size,
+        crossover_probability: 0.0,
+        ..Conf::default()
+    }
+}
+
+#[test]
+fn elitism_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        elitism: 1,
+        ..asexual_conf(4)
+    };
+    let mut genus = full_genus(4, GENOME_SIZE, &conf);
+
+    // every offspring is worse than its parent
+    let mut rng = rand::thread_rng();
+    let mut genus = replace_generation(&mut genus, &conf, |individual| individual.mutate(&mut rng));
+    assert_eq!(genus.count_individuals(), 4);
+    assert!(selectable_fitnesses(&mut genus, &conf).contains(&(GENOME_SIZE as f32)));
+}
+
+#[test]
+fn champion_preservation_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        preserve_champion: true,
+        ..asexual_conf(2)
+    };
+    let mut genus = full_genus(2, GENOME_SIZE, &conf);
+
+    // the offspring is incompatible with its species, which is left without individuals
+    let mut genus = replace_generation(&mut genus, &conf, |individual| individual.genome = vec![false; GENOME_SIZE]);
+    assert_eq!(genus.count_individuals(), 2);
+    assert_eq!(genus.species_count(), 2);
+    assert!(selectable_fitnesses(&mut genus, &conf).contains(&(GENOME_SIZE as f32)));
+}
+
+#[test]
+fn champion_preservation_single_individual_donor_test() {
+    const GENOME_SIZE: usize = 10;
+    // all the offspring go to the last species, none to the species of the champion
+    let conf = Conf {
+        preserve_champion: true,
+        offspring_rounding: OffspringRounding::Custom(|shares, offspring| {
+            let mut amounts = vec![0; shares.len()];
+            amounts[shares.len() - 1] = offspring;
+            amounts
+        }),
+        ..asexual_conf(2)
+    };
+    let mut genus = genus_with_genomes(2, &conf, |i| vec![i == 0; GENOME_SIZE]);
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    assert_eq!(genus.species_count(), 2);
+
+    // the second offspring is incompatible with its species: both species left have one individual
+    let offspring = Cell::new(0);
+    let genus = replace_generation(&mut genus, &conf, |individual| {
+        offspring.set(offspring.get() + 1);
+        if offspring.get() == 2 {
+            individual.genome = (0..GENOME_SIZE).map(|gene| gene < GENOME_SIZE / 2).collect();
         }
+    });
+    assert_eq!(genus.count_individuals(), 2);
+    assert_eq!(genus.species_count(), 2);
+    assert_eq!(genus.best_fitness(), Some(GENOME_SIZE as f32));
+}
+
+#[test]
+fn best_individual_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf::default();
+    let mut genus = genus_with_genomes(4, &conf, |i| (0..GENOME_SIZE).map(|gene| gene <= i).collect());
+    assert!(genus.best_individual().is_none());
+    assert_eq!(genus.best_fitness(), None);
+
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    assert_eq!(genus.best_individual().map(|individual| individual.id), Some(3));
+    assert_eq!(genus.best_fitness(), Some(4.0));
+    // same champion through the cache
+    genus.update(&conf);
+    assert_eq!(genus.best_fitness(), Some(4.0));
+}
+
+#[test]
+fn iter_individuals_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf::default();
+    let mut genus = full_genus(4, GENOME_SIZE, &conf);
+    genus.update(&conf);
+    assert_eq!(genus.iter_individuals().count(), genus.count_individuals());
+
+    for individual in genus.iter_individuals_mut() {
+        individual.genome = vec![false; GENOME_SIZE];
+        individual.evaluate();
     }
+    assert!(genus.iter_individuals().all(|individual| individual.fitness == Some(0.0)));
+    assert_eq!(genus.best_fitness(), Some(0.0));
+}
+
+#[test]
+fn lineage_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = asexual_conf(2);
+    let mut genus = full_genus(2, GENOME_SIZE, &conf);
+    assert_eq!(genus.iter_species().next().unwrap().parent_species_id(), None);
+
+    // the offspring are incompatible with their species and found a new one
+    let mut genus = replace_generation(&mut genus, &conf, |individual| individual.genome = vec![false; GENOME_SIZE]);
+    let genus = replace_generation(&mut genus, &conf, |individual| individual.genome = vec![true; GENOME_SIZE]);
+    assert_eq!(genus.species_count(), 1);
+    let species = genus.iter_species().next().unwrap();
+    assert_eq!(species.id, 3);
+    assert_eq!(species.parent_species_id(), Some(2));
+    assert_eq!(genus.parent_species(2), Some(1));
+    assert_eq!(genus.lineage(3), vec![2, 1]);
+    assert!(genus.lineage(1).is_empty());
+}
+
+#[test]
+fn k_medoids_speciation_test() {
+    const GENOME_SIZE: usize = 10;
+    let population = || [0, 1, 2, 8, 9, 10].iter().enumerate().map(|(id, &true_genes)| {
+        let mut individual = IndividualTest::empty(id, GENOME_SIZE);
+        individual.genome = (0..GENOME_SIZE).map(|gene| gene < true_genes).collect();
+        individual
+    });
+    let conf = Conf {
+        speciation_strategy: SpeciationStrategy::KMedoids { species: 2, max_iterations: 10 },
+        ..Conf::default()
+    };
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(&conf, population());
+    let species: Vec<(usize, Vec<usize>)> = genus.iter_species()
+        .map(|species| (species.representative().unwrap().id, species.iter().map(|i| i.id).collect()))
+        .collect();
+    assert_eq!(species, vec![(1, vec![1, 0, 2]), (4, vec![4, 3, 5])]);
+
+    // the number of species is not bound to the compatibility threshold
+    let conf = Conf {
+        speciation_strategy: SpeciationStrategy::KMedoids { species: 3, max_iterations: 10 },
+        ..conf
+    };
+    genus.speciate(&conf, population());
+    assert_eq!(genus.species_count(), 3);
+    assert_eq!(genus.count_individuals(), 6);
+    assert!(!genus.speciation_fallback());
+}
+
+#[test]
+fn k_medoids_speciation_fallback_test() {
+    let conf = Conf {
+        speciation_strategy: SpeciationStrategy::KMedoids { species: 2, max_iterations: 10 },
+        ..Conf::default()
+    };
+    // without a distance the (all compatible) individuals are grouped by first match
+    let target = vec![true; 6];
+    let mut genus: Genus<BorrowingIndividual, f32> = Genus::new();
+    genus.speciate(&conf, (0..4).map(|_| BorrowingIndividual { target: &target, genome: vec![false; 6], fitness: None }));
+    assert!(genus.speciation_fallback());
+    assert_eq!(genus.species_count(), 1);
+    assert_eq!(genus.count_individuals(), 4);
+
+    genus.speciate(&Conf::default(), (0..4).map(|_| BorrowingIndividual { target: &target, genome: vec![false; 6], fitness: None }));
+    assert!(!genus.speciation_fallback());
+}
+
+#[test]
+fn interspecies_mating_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 4,
+        interspecies_mating_rate: 1.0,
+        ..Conf::default()
+    };
+    let mut genus = genus_with_genomes(4, &conf, |i| vec![i % 2 == 0; GENOME_SIZE]);
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    assert_eq!(genus.species_count(), 2);
+
+    let crossovers = Cell::new(0);
+    genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |_it| panic!("parents must come from different species"),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, parent2: &IndividualTest| {
+                assert_ne!(parent1.genome, parent2.genome);
+                crossovers.set(crossovers.get() + 1);
+                parent1.clone()
+            },
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    assert_eq!(crossovers.get(), 4);
+}
+
+#[test]
+fn crossover_distance_threshold_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = |crossover_distance_threshold| Conf {
+        total_population_size: 4,
+        crossover_probability: 1.0,
+        compatibility_threshold: Some(5.0),
+        crossover_compatibility_guard: true,
+        crossover_distance_threshold,
+        ..Conf::default()
+    };
+    // one species, the two halves of the population at distance 4
+    let guarded_crossovers = |conf: &Conf| {
+        let mut genus = genus_with_genomes(4, conf, |i| (0..GENOME_SIZE).map(|gene| i < 2 || gene >= 4).collect());
+        genus.ensure_evaluated_population(|individual| individual.evaluate());
+        assert_eq!(genus.species_count(), 1);
+        let crossovers = Cell::new(0);
+        let generated_individuals = genus.update(conf)
+            .generate_new_individuals(
+                conf,
+                &mut |mut it| it.next().unwrap(),
+                // always two parents from different halves
+                &mut |it| {
+                    let individuals: Vec<&IndividualTest> = it.collect();
+                    let parent2 = individuals.iter().find(|individual| individual.genome != individuals[0].genome).unwrap();
+                    (individuals[0], *parent2)
+                },
+                &mut |parent: &IndividualTest| parent.clone(),
+                &mut |parent1: &IndividualTest, parent2: &IndividualTest| {
+                    assert!(are_crossover_compatible(parent1, parent2, conf));
+                    crossovers.set(crossovers.get() + 1);
+                    parent1.clone()
+                },
+                &mut |_individual: &mut IndividualTest| {},
+            );
+        assert_eq!(generated_individuals.crossover_guard_triggers + crossovers.get(), 4);
+        generated_individuals.crossover_guard_triggers
+    };
+
+    // the parents are compatible for the speciation
+    assert_eq!(guarded_crossovers(&conf(None)), 0);
+    assert_eq!(guarded_crossovers(&conf(Some(4.0))), 0);
+    // but too distant for the crossover threshold
+    assert_eq!(guarded_crossovers(&conf(Some(3.0))), 4);
+}
+
+#[test]
+fn interspecies_mating_locked_guarded_test() {
+    const GENOME_SIZE: usize = 9;
+    let conf = Conf {
+        total_population_size: 6,
+        interspecies_mating_rate: 1.0,
+        crossover_compatibility_guard: true,
+        ..Conf::default()
+    };
+    // three niches: all false, all true and the first 5 genes true
+    let is_mixed = |individual: &IndividualTest| individual.genome[0] && !individual.genome[GENOME_SIZE - 1];
+    let mut genus = genus_with_genomes(6, &conf, |i| (0..GENOME_SIZE).map(|gene| i % 3 == 1 || (i % 3 == 2 && gene < 5)).collect());
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    assert_eq!(genus.species_count(), 3);
+    assert!(genus.lock_species(3));
+
+    let crossovers = Cell::new(0);
+    let generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |_it| panic!("parents must come from different species"),
+            &mut |_parent: &IndividualTest| panic!("interspecies crossovers must not be guarded"),
+            &mut |parent1: &IndividualTest, parent2: &IndividualTest| {
+                assert!(!is_mixed(parent1) && !is_mixed(parent2), "the locked species must not mate");
+                assert_ne!(parent1.genome, parent2.genome);
+                crossovers.set(crossovers.get() + 1);
+                parent1.clone()
+            },
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    assert_eq!(crossovers.get(), 4);
+    assert_eq!(generated_individuals.crossover_guard_triggers, 0);
+}
+
+#[test]
+fn survival_threshold_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        survival_threshold: 0.5,
+        ..asexual_conf(4)
+    };
+    let mut genus = genus_with_genomes(4, &conf, |i| (0..GENOME_SIZE).map(|gene| gene >= i).collect());
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    assert_eq!(genus.species_count(), 1);
+
+    let mut fitnesses = selectable_fitnesses(&mut genus, &conf);
+    fitnesses.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    fitnesses.dedup();
+    assert_eq!(fitnesses, vec![10.0, 9.0]);
+}
+
+#[test]
+fn min_offspring_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = |min_offspring| Conf {
+        min_offspring,
+        ..asexual_conf(4)
+    };
+    let genus = |conf: &Conf| {
+        let mut genus = genus_with_genomes(4, conf, |i| vec![i > 0; GENOME_SIZE]);
+        genus.ensure_evaluated_population(|individual| individual.evaluate());
+        genus
+    };
+
+    // the species with fitness 0 is wiped out by rounding without the quota
+    assert!(!selectable_fitnesses(&mut genus(&conf(0)), &conf(0)).contains(&0.0));
+    assert!(selectable_fitnesses(&mut genus(&conf(1)), &conf(1)).contains(&0.0));
+}
+
+#[test]
+#[should_panic(expected = "Minimum offspring quotas (6 in total) exceed the 4 offspring")]
+fn min_offspring_exceeding_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        min_offspring: 3,
+        ..asexual_conf(4)
+    };
+    let mut genus = genus_with_genomes(4, &conf, |i| vec![i > 0; GENOME_SIZE]);
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    assert_eq!(genus.species_count(), 2);
+    selectable_fitnesses(&mut genus, &conf);
+}
+
+#[test]
+fn max_species_test() {
+    const GENOME_SIZE: usize = 12;
+    // three mutually incompatible individuals
+    let population = || (0..3).map(|i| {
+        let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+        individual.genome = (0..GENOME_SIZE).map(|gene| gene < 5 * i).collect();
+        individual
+    });
+
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(&Conf::default(), population());
+    assert_eq!(genus.species_count(), 3);
+
+    let conf = Conf {
+        max_species: Some(2),
+        ..Conf::default()
+    };
+    genus.speciate(&conf, population());
+    assert_eq!(genus.species_count(), 2);
+    assert_eq!(genus.count_individuals(), 3);
+}
+
+#[test]
+fn time_boxed_evaluation_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 6,
+        ..Conf::default()
+    };
+    let mut genus = full_genus(6, GENOME_SIZE, &conf);
+    let mut generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |individual: &mut IndividualTest| individual.fitness = None,
+        );
+    assert_eq!(generated_individuals.evaluate_within(Duration::ZERO, |individual| individual.evaluate()), 0);
+    assert_eq!(generated_individuals.pending_evaluations(), 6);
+
+    // the second evaluation runs out of time
+    let evaluations = Cell::new(0);
+    let evaluated = generated_individuals.evaluate_within(Duration::from_millis(20), |individual| {
+        evaluations.set(evaluations.get() + 1);
+        if evaluations.get() == 2 {
+            std::thread::sleep(Duration::from_millis(40));
+        }
+        individual.evaluate()
+    });
+    assert_eq!(evaluated, 2);
+    assert_eq!(generated_individuals.pending_evaluations(), 4);
+
+    // the individuals without fitness are carried over
+    let mut genus = genus.next_generation(&conf, generated_individuals, |mut new_individuals, _old_individuals, target_population| {
+        new_individuals.truncate(target_population);
+        new_individuals
+    });
+    evaluations.set(0);
+    genus.ensure_evaluated_population(|individual| {
+        evaluations.set(evaluations.get() + 1);
+        individual.evaluate()
+    });
+    assert_eq!(evaluations.get(), 4);
+}
+
+/// Genus with an evaluated species (id 1) and a species (id 2) where no individual is evaluated
+fn half_evaluated_genus(conf: &Conf) -> Genus<IndividualTest, f32> {
+    const GENOME_SIZE: usize = 10;
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(conf, (0..4).map(|i| {
+        let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+        individual.genome = vec![i < 2; GENOME_SIZE];
+        if i < 2 {
+            individual.evaluate();
+        }
+        individual
+    }));
+    assert_eq!(genus.species_count(), 2);
+    genus
+}
+
+#[test]
+fn unevaluated_species_treat_as_zero_test() {
+    let conf = Conf {
+        total_population_size: 4,
+        unevaluated_species_policy: UnevaluatedSpeciesPolicy::TreatAsZero,
+        ..Conf::default()
+    };
+    let mut genus = half_evaluated_genus(&conf);
+    genus.update(&conf);
+    assert!(genus.skipped_species().is_empty());
+    // the unevaluated species still gets adjusted fitness, from the zero fitness epsilon
+    let mean_adjusted_fitness = |id| genus.iter_species().find(|species| species.id == id).unwrap()
+        .mean_adjusted_fitness().unwrap();
+    assert!(mean_adjusted_fitness(2) > 0.0);
+    assert!(mean_adjusted_fitness(2) < mean_adjusted_fitness(1) / 1000.0);
+}
+
+#[test]
+fn unevaluated_species_skip_test() {
+    let conf = Conf {
+        total_population_size: 4,
+        unevaluated_species_policy: UnevaluatedSpeciesPolicy::Skip,
+        ..Conf::default()
+    };
+    let mut genus = half_evaluated_genus(&conf);
+    let generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    assert_eq!(genus.skipped_species(), &[2]);
+    assert_eq!(generated_individuals.skipped_species, 1);
+    // the skipped species gets no offspring
+    assert!(generated_individuals.need_evaluation.iter().all(|individual| individual.borrow().genome[0]));
+}
 
-    println!("Evolution took {} generations to complete with a fitness of {}", generation_n, best_fitness);
+#[test]
+#[should_panic(expected = "Species 2 has no evaluated individuals")]
+fn unevaluated_species_panic_test() {
+    let conf = Conf {
+        total_population_size: 4,
+        unevaluated_species_policy: UnevaluatedSpeciesPolicy::Panic,
+        ..Conf::default()
+    };
+    let mut genus = half_evaluated_genus(&conf);
+    genus.update(&conf);
+}
+
+#[test]
+fn fitness_collapse_test() {
+    const GENOME_SIZE: usize = 10;
+    // skipping the unevaluated species leaves no adjusted fitness at all
+    let conf = Conf {
+        total_population_size: 4,
+        unevaluated_species_policy: UnevaluatedSpeciesPolicy::Skip,
+        ..Conf::default()
+    };
+    let mut genus = genus_with_genomes(4, &conf, |_| vec![false; GENOME_SIZE]);
+
+    let mut generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    assert_eq!(generated_individuals.need_evaluation.len(), 4);
+    assert_eq!(genus.fitness_collapses(), 1);
+    generated_individuals.evaluate(|individual| individual.evaluate());
+    let mut genus = genus.next_generation(&conf, generated_individuals, elitist_population_management);
+    assert_eq!(genus.count_individuals(), 4);
+    assert_eq!(genus.fitness_collapses(), 1);
+
+    // the offspring are evaluated, the next allocation goes by fitness again
+    let genus = replace_generation(&mut genus, &conf, |_individual| {});
+    assert_eq!(genus.fitness_collapses(), 1);
+}
+
+#[test]
+fn fitness_history_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        fitness_history_length: 2,
+        ..asexual_conf(4)
+    };
+    let mut genus = full_genus(4, GENOME_SIZE, &conf);
+    // the offspring are identical to the parents, the species survives with a constant fitness
+    for _ in 0..3 {
+        genus = replace_generation(&mut genus, &conf, |_individual| {});
+    }
+    assert_eq!(genus.species_count(), 1);
+    let species = genus.iter_species().next().unwrap();
+    assert_eq!(species.fitness_history().iter().copied().collect::<Vec<_>>(), vec![Some(10.0); 2]);
+    assert_eq!(species.fitness_improvement(1), Some(0.0));
+    assert_eq!(species.fitness_improvement(2), None);
+}
+
+#[test]
+fn mass_extinction_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 4,
+        mass_extinction: Some(MassExtinction { stagnation: 2, surviving_species: 1 }),
+        ..Conf::default()
+    };
+    let mut genus = genus_with_genomes(4, &conf, |i| (0..GENOME_SIZE).map(|gene| i % 2 == 0 || gene < GENOME_SIZE / 2).collect());
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    assert_eq!(genus.species_count(), 2);
+
+    // the offspring are exact copies, the fitness never improves
+    for generation in 0..2 {
+        genus = replace_generation(&mut genus, &conf, |_individual| {});
+        assert_eq!(genus.generations_without_improvement(), generation);
+        assert_eq!(genus.species_count(), 2);
+        assert_eq!(genus.last_mass_extinction(), None);
+    }
+    let genus = replace_generation(&mut genus, &conf, |_individual| {});
+    assert_eq!(genus.generations_without_improvement(), 0);
+    assert_eq!(genus.species_count(), 1);
+    assert_eq!(genus.count_individuals(), 4);
+    // triggered by the update of generation 2
+    assert_eq!(genus.generation(), 3);
+    assert_eq!(genus.last_mass_extinction(), Some(2));
+}
+
+#[test]
+fn hypermutation_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 4,
+        hypermutation: Some(Hypermutation { stagnation: 2, factor: 4.0, annealing_rate: 0.5 }),
+        ..Conf::default()
+    };
+    let mut genus = genus_with_genomes(4, &conf, |_| vec![false; GENOME_SIZE]);
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+
+    // the offspring are exact copies, the fitness never improves
+    for _ in 0..2 {
+        genus = replace_generation(&mut genus, &conf, |_individual| {});
+        assert_eq!(genus.hypermutation_factor(), 1.0);
+    }
+    genus = replace_generation(&mut genus, &conf, |_individual| {});
+    assert_eq!(genus.hypermutation_factor(), 4.0);
+    assert_eq!(genus.mutation_strength(&conf), 4.0);
+
+    // the improvement of these offspring is seen by the next update, then the factor anneals
+    genus = replace_generation(&mut genus, &conf, |individual| individual.genome[0] = true);
+    assert_eq!(genus.hypermutation_factor(), 4.0);
+    genus = replace_generation(&mut genus, &conf, |_individual| {});
+    assert_eq!(genus.hypermutation_factor(), 2.0);
+    genus = replace_generation(&mut genus, &conf, |_individual| {});
+    assert_eq!(genus.hypermutation_factor(), 1.0);
+}
+
+#[test]
+fn hypermutation_validation_test() {
+    let conf = |factor: f64, annealing_rate: f64| Conf {
+        hypermutation: Some(Hypermutation { stagnation: 2, factor, annealing_rate }),
+        ..Conf::default()
+    };
+    assert!(conf(4.0, 0.5).validate().is_ok());
+    assert!(conf(1.0, 0.0).validate().is_ok());
+    assert!(conf(0.5, 0.5).validate().is_err());
+    assert!(conf(f64::INFINITY, 0.5).validate().is_err());
+    assert!(conf(4.0, 1.5).validate().is_err());
+    assert!(conf(4.0, -0.5).validate().is_err());
+    assert!(conf(4.0, f64::NAN).validate().is_err());
+}
+
+#[test]
+fn zero_fitness_epsilon_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 4,
+        zero_fitness_epsilon: 0.0,
+        ..Conf::default()
+    };
+    let mut genus = genus_with_genomes(4, &conf, |_| vec![false; GENOME_SIZE]);
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+
+    // zero total adjusted fitness, the offspring are allocated by species size
+    let genus = replace_generation(&mut genus, &conf, |_individual| {});
+    assert_eq!(genus.count_individuals(), 4);
+}
+
+#[test]
+fn negative_fitness_test() {
+    const GENOME_SIZE: usize = 10;
+    let negative_evaluation = |individual: &mut IndividualTest| {
+        let fitness = individual.evaluate() - GENOME_SIZE as f32;
+        individual.fitness = Some(fitness);
+        fitness
+    };
+    let population = || (0..4).map(|i| {
+        let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+        individual.genome[0] = i % 2 == 0;
+        individual
+    });
+
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(&Conf::default(), population());
+    genus.ensure_evaluated_population(negative_evaluation);
+    assert!(genus.try_update(&Conf::default()).is_err());
+
+    let conf = Conf {
+        total_population_size: 4,
+        negative_fitness_policy: NegativeFitnessPolicy::ShiftByMinimum,
+        ..Conf::default()
+    };
+    assert!(genus.try_update(&conf).is_ok());
+    let mut generated_individuals = genus
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    generated_individuals.evaluate(negative_evaluation);
+    let genus = genus.next_generation(&conf, generated_individuals, elitist_population_management);
+    assert_eq!(genus.count_individuals(), 4);
+}
+
+#[test]
+fn rank_adjusted_fitness_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = |adjusted_fitness_mode| Conf {
+        adjusted_fitness_mode,
+        ..asexual_conf(6)
+    };
+    // an outlier fitness takes every offspring with raw fitness sharing
+    let weak_offspring = |conf: &Conf| {
+        let mut genus = genus_with_genomes(6, conf, |i| vec![i % 2 == 0; GENOME_SIZE]);
+        genus.ensure_evaluated_population(|individual| {
+            let fitness = if individual.genome[0] { 1_000_000.0 } else { 1.0 };
+            individual.fitness = Some(fitness);
+            fitness
+        });
+        selectable_fitnesses(&mut genus, conf).into_iter()
+            .filter(|fitness| *fitness == 1.0)
+            .count()
+    };
+
+    assert_eq!(weak_offspring(&conf(AdjustedFitnessMode::Raw)), 0);
+    // 2 offspring, every selection sees the 3 individuals of the species
+    assert_eq!(weak_offspring(&conf(AdjustedFitnessMode::Rank)), 2 * 3);
+
+    // a high temperature flattens the fitness differences, a low one amplifies them
+    let boltzmann = |initial_temperature| AdjustedFitnessMode::Boltzmann { initial_temperature, cooling_rate: 0.5, min_temperature: 1.0 };
+    assert_eq!(weak_offspring(&conf(boltzmann(10_000_000.0))), 2 * 3);
+    assert_eq!(weak_offspring(&conf(boltzmann(1.0))), 0);
+    assert_eq!(boltzmann(8.0).temperature(2), Some(2.0));
+    assert_eq!(boltzmann(8.0).temperature(10), Some(1.0));
+}
+
+#[test]
+fn offspring_rounding_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = |offspring_rounding| Conf {
+        offspring_rounding,
+        ..asexual_conf(5)
+    };
+    // three singleton species with shares 1.72, 1.64 and 1.64
+    let best_species_offspring = |conf: &Conf| {
+        let mut genus = genus_with_genomes(3, conf, |i| (0..GENOME_SIZE).map(|gene| i == 0 || (gene < GENOME_SIZE / 2) == (i == 1)).collect());
+        genus.ensure_evaluated_population(|individual| {
+            let fitness = if individual.genome.iter().all(|gene| *gene) { 10.0 } else { 9.5 };
+            individual.fitness = Some(fitness);
+            fitness
+        });
+        assert_eq!(genus.species_count(), 3);
+        selectable_fitnesses(&mut genus, conf).into_iter()
+            .filter(|fitness| *fitness == 10.0)
+            .count()
+    };
+
+    assert_eq!(best_species_offspring(&conf(OffspringRounding::FloorBestSpecies)), 3);
+    assert_eq!(best_species_offspring(&conf(OffspringRounding::LargestRemainder)), 2);
+    assert!((1..=2).contains(&best_species_offspring(&conf(OffspringRounding::StochasticRemainder))));
+    // 2 + 2 + 2, the worst species loses the excess
+    assert_eq!(best_species_offspring(&conf(OffspringRounding::RoundHalfEven)), 2);
+    let everything_to_first = |shares: &[f64], total: usize| {
+        let mut amounts = vec![0; shares.len()];
+        amounts[0] = total;
+        amounts
+    };
+    assert_eq!(best_species_offspring(&conf(OffspringRounding::Custom(everything_to_first))), 5);
+}
+
+#[test]
+fn offspring_rounding_halves_test() {
+    // 2.5 is rounded down to 2, 1.5 up to 2
+    assert_eq!(rounded_species_offspring(OffspringRounding::RoundHalfEven), vec![2, 2, 1, 1]);
+    assert_eq!(rounded_species_offspring(OffspringRounding::LargestRemainder), vec![3, 1, 1, 1]);
+    let favour_last = |_shares: &[f64], _total: usize| vec![1, 1, 2, 2];
+    assert_eq!(rounded_species_offspring(OffspringRounding::Custom(favour_last)), vec![1, 1, 2, 2]);
+}
+
+#[test]
+#[should_panic(expected = "Custom offspring rounding returned 6 amounts summing up to 6 instead of 4 summing up to 6")]
+fn offspring_rounding_custom_length_test() {
+    rounded_species_offspring(OffspringRounding::Custom(|_shares, total| vec![1; total]));
+}
+
+#[test]
+#[should_panic(expected = "Custom offspring rounding returned 4 amounts summing up to 3 instead of 4 summing up to 6")]
+fn offspring_rounding_custom_sum_test() {
+    rounded_species_offspring(OffspringRounding::Custom(|shares, _total| {
+        let mut amounts = vec![1; shares.len()];
+        amounts[0] = 0;
+        amounts
+    }));
+}
+
+/// Offspring of four singleton species (a different block of genes set) with shares 2.5, 1.5, 1 and 1
+fn rounded_species_offspring(offspring_rounding: OffspringRounding) -> Vec<usize> {
+    const GENOME_SIZE: usize = 12;
+    let conf = Conf {
+        young_age_fitness_boost: 1.0,
+        offspring_rounding,
+        ..asexual_conf(6)
+    };
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.speciate(&conf, (0..4).map(|i| {
+        let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+        individual.genome = (0..GENOME_SIZE).map(|gene| gene / 3 == i).collect();
+        individual.fitness = Some([5.0, 3.0, 2.0, 2.0][i]);
+        individual
+    }));
+    assert_eq!(genus.species_count(), 4);
+    let generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    let mut offspring = vec![0; 4];
+    for individual in &generated_individuals.need_evaluation {
+        offspring[individual.borrow().genome.iter().position(|gene| *gene).unwrap() / 3] += 1;
+    }
+    offspring
+}
+
+#[test]
+fn population_round_trip_test() {
+    const GENOME_SIZE: usize = 10;
+    let mut rng = rand::thread_rng();
+    let mut population: Population<IndividualTest> = bootstrap::from_factory(6, |id| IndividualTest::random(id, GENOME_SIZE, &mut rng)).into();
+    assert_eq!(population.evaluate_missing(|individual| individual.evaluate()), 6);
+    assert_eq!(population.evaluate_missing(|individual| individual.evaluate()), 0);
+    let best_fitness = population.best().unwrap().fitness();
+
+    let serialized = population.serialize().unwrap();
+    let restored: Population<IndividualTest> = Population::deserialize(&serialized).unwrap();
+    assert_eq!(restored.len(), 6);
+
+    let genus = Genus::from_population(&Conf::default(), population);
+    assert_eq!(genus.count_individuals(), 6);
+    assert_eq!(genus.to_population().len(), 6);
+    let population = genus.into_population();
+    assert_eq!(population.len(), 6);
+    assert_eq!(population.best().unwrap().fitness(), best_fitness);
+
+    // the seeded conversion is reproducible
+    let population: Population<IndividualTest> = population.into_iter().collect();
+    let genus_a = Genus::from_population_with_seed(&Conf::default(), population.clone(), 5);
+    let genus_b = Genus::from_population_with_seed(&Conf::default(), population, 5);
+    assert_eq!(genus_a.fork_rng().gen::<u64>(), genus_b.fork_rng().gen::<u64>());
+}
+
+#[test]
+fn nan_evaluation_test() {
+    let mut population: Population<IndividualTest> = vec![IndividualTest::with_fitness(0, None)].into();
+    let evaluated = population.evaluate_missing(|individual: &mut IndividualTest| {
+        individual.fitness = Some(f32::NAN);
+        f32::NAN
+    });
+    assert_eq!(evaluated, 1);
+}
+
+#[test]
+#[should_panic(expected = "the evaluation has to store the fitness it returns in the individual")]
+fn evaluation_mismatch_test() {
+    let mut population: Population<IndividualTest> = vec![IndividualTest::with_fitness(0, None)].into();
+    population.evaluate_missing(|individual: &mut IndividualTest| {
+        individual.fitness = Some(1.0);
+        2.0
+    });
+}
+
+#[test]
+fn checkpoint_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = asexual_conf(6);
+    let mut genus = genus_with_genomes(6, &conf, |i| (0..GENOME_SIZE).map(|gene| gene < i * 2).collect());
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    let genus = run_generation(&mut genus, &conf, &conf, &Cell::new(100));
+    let checkpoint = genus.checkpoint("{\"seed\":1}").unwrap();
+    assert_eq!(checkpoint.generation, 1);
+    assert_eq!(checkpoint.individuals.len(), 6);
+
+    let mut written = Vec::new();
+    checkpoint.write(&mut written).unwrap();
+    let read = Checkpoint::read(written.as_slice()).unwrap();
+    assert_eq!(read, checkpoint);
+
+    // the species and the generation are restored
+    let restored: Genus<IndividualTest, f32> = Genus::from_checkpoint(&conf, &read, 1).unwrap();
+    assert_eq!(restored.generation(), 1);
+    assert_eq!(restored.species_count(), genus.species_count());
+    assert_eq!(restored.checkpoint("{\"seed\":1}").unwrap(), checkpoint);
+
+    // a population without species is speciated
+    let population = genus.to_population();
+    let checkpoint = population.checkpoint(3, "").unwrap();
+    assert!(checkpoint.individuals.iter().all(|individual| individual.species_id.is_none()));
+    let restored: Genus<IndividualTest, f32> = Genus::from_checkpoint(&conf, &checkpoint, 1).unwrap();
+    assert_eq!(restored.generation(), 3);
+    assert_eq!(restored.count_individuals(), 6);
+    assert_eq!(Population::<IndividualTest>::from_checkpoint(&checkpoint).unwrap().len(), 6);
+
+    assert_eq!(Checkpoint::read("rustneat-checkpoint 1\ngeneration 1\nmanifest \nindividual - 0g\n".as_bytes()).unwrap_err().kind(),
+               std::io::ErrorKind::InvalidData);
+    assert!(Checkpoint { manifest: "two\nlines".to_string(), ..Checkpoint::default() }.write(Vec::new()).is_err());
+}
+
+#[test]
+fn elite_slots_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 4,
+        elitism: 1,
+        ..Conf::default()
+    };
+    let mut genus = full_genus(4, GENOME_SIZE, &conf);
+    let mut generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    // the elite does not need to be generated and evaluated
+    assert_eq!(generated_individuals.elite_slots, vec![1]);
+    assert_eq!(generated_individuals.need_evaluation.len(), 3);
+    generated_individuals.evaluate(|individual| individual.evaluate());
+    let genus = genus.next_generation(&conf, generated_individuals, elitist_population_management);
+    assert_eq!(genus.count_individuals(), 4);
+}
+
+#[test]
+fn duplicate_offspring_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        duplicate_offspring_retries: 3,
+        ..asexual_conf(4)
+    };
+    let generate = |genus: &mut Genus<IndividualTest, f32>, mutate: &mut dyn FnMut(&mut IndividualTest)| {
+        genus.update(&conf)
+            .generate_new_individuals(
+                &conf,
+                &mut |mut it| it.next().unwrap(),
+                &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+                &mut |parent: &IndividualTest| parent.clone(),
+                &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+                &mut |individual: &mut IndividualTest| mutate(individual),
+            )
+    };
+
+    // clones of the parents are re-generated until the retries run out, then kept
+    let mut genus = full_genus(4, GENOME_SIZE, &conf);
+    let generated_individuals = generate(&mut genus, &mut |_individual| {});
+    assert_eq!(generated_individuals.duplicate_offspring_rejections, 4 * 3);
+    assert_eq!(generated_individuals.need_evaluation.len(), 4);
+
+    // every other attempt repeats the previous genome
+    let mut genus = full_genus(4, GENOME_SIZE, &conf);
+    let mut attempt = 0;
+    let generated_individuals = generate(&mut genus, &mut |individual| {
+        individual.genome[attempt / 2] = false;
+        attempt += 1;
+    });
+    assert_eq!(generated_individuals.duplicate_offspring_rejections, 3);
+    let genomes: HashSet<Vec<bool>> = generated_individuals.need_evaluation.iter()
+        .map(|individual| individual.borrow().genome.clone())
+        .collect();
+    assert_eq!(genomes.len(), 4);
+}
+
+#[test]
+fn orphan_policy_test() {
+    const GENOME_SIZE: usize = 10;
+    let population = || (0..4).map(|i| {
+        let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+        individual.genome = (0..GENOME_SIZE).map(|gene| i < 2 || gene < 5).collect();
+        individual.evaluate();
+        individual
+    });
+    // incompatible with both species
+    let mutate = |individual: &mut IndividualTest| individual.genome = (0..GENOME_SIZE).map(|gene| gene >= 4).collect();
+
+    for (orphan_policy, new_species) in [(OrphanPolicy::NewSpecies, true), (OrphanPolicy::NearestSpecies, false)] {
+        let conf = Conf {
+            orphan_policy,
+            ..asexual_conf(4)
+        };
+        let mut genus: Genus<IndividualTest, f32> = Genus::new();
+        genus.speciate(&conf, population());
+        assert_eq!(genus.species_count(), 2);
+        let genus = replace_generation(&mut genus, &conf, mutate);
+        assert_eq!(genus.count_individuals(), 4);
+        assert_eq!(genus.iter_species().any(|species| species.id > 2), new_species);
+    }
+
+    // every other attempt is incompatible
+    let conf = Conf {
+        orphan_policy: OrphanPolicy::Regenerate { attempts: 1 },
+        ..asexual_conf(4)
+    };
+    let mut genus = full_genus(4, GENOME_SIZE, &conf);
+    let mut attempt = 0;
+    let generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |individual: &mut IndividualTest| {
+                if attempt % 2 == 0 {
+                    individual.genome = vec![false; GENOME_SIZE];
+                }
+                attempt += 1;
+            },
+        );
+    assert_eq!(generated_individuals.orphan_regenerations, 4);
+    assert!(generated_individuals.orphans.is_empty());
+}
+
+#[test]
+fn species_id_policy_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = asexual_conf(2);
+    // restored genus
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.set_next_species_id(10);
+    genus.speciate(&conf, (0..2).map(|i| IndividualTest::empty(i, GENOME_SIZE)));
+    assert_eq!(genus.iter_species().next().unwrap().id, 10);
+    assert_eq!(genus.next_species_id(), 11);
+
+    let conf = Conf {
+        species_id_policy: SpeciesIdPolicy::ReuseExtinct,
+        ..conf
+    };
+    let mut genus = full_genus(2, GENOME_SIZE, &conf);
+    let mut genus = replace_generation(&mut genus, &conf, |individual| individual.genome = vec![false; GENOME_SIZE]);
+    assert_eq!(genus.iter_species().next().unwrap().id, 2);
+    // species 1 is extinct, its id is reused
+    let genus = replace_generation(&mut genus, &conf, |individual| individual.genome = vec![true; GENOME_SIZE]);
+    assert_eq!(genus.iter_species().next().unwrap().id, 1);
+    assert_eq!(genus.lineage(1), vec![2]);
+    assert_eq!(genus.next_species_id(), 3);
+}
+
+#[test]
+fn minimization_test() {
+    const GENOME_SIZE: usize = 10;
+    // the fitness is positive, the default NegativeFitnessPolicy::Reject accepts it
+    let conf = asexual_conf(4);
+    // the cost is the number of true genes
+    let mut genus: Genus<Minimize<IndividualTest>, f32> = Genus::new();
+    genus.speciate(&conf, (0..4).map(|i| {
+        let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+        individual.genome = vec![i % 2 == 0; GENOME_SIZE];
+        Minimize(individual)
+    }));
+    genus.ensure_evaluated_population(|individual| individual.evaluate(|individual| individual.evaluate()));
+    assert_eq!(genus.best_fitness(), Some(1.0));
+    assert_eq!(genus.best_individual().unwrap().cost(), Some(0.0));
+
+    // the species with the lowest cost gets every offspring
+    let selected_costs = RefCell::new(Vec::new());
+    genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| {
+                let individual = it.next().unwrap();
+                selected_costs.borrow_mut().push(individual.cost().unwrap());
+                individual
+            },
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &Minimize<IndividualTest>| parent.clone(),
+            &mut |parent1: &Minimize<IndividualTest>, _parent2: &Minimize<IndividualTest>| parent1.clone(),
+            &mut |_individual: &mut Minimize<IndividualTest>| {},
+        );
+    assert_eq!(selected_costs.into_inner(), vec![0.0; 4]);
+}
+
+#[test]
+fn cost_to_fitness_test() {
+    assert_eq!(cost_to_fitness(0.0), 1.0);
+    assert_eq!(cost_to_fitness(1.0), 0.5);
+    assert_eq!(cost_to_fitness(-1.0), 2.0);
+    let costs = [-100.0, -1.5, -0.1, 0.0, 0.1, 1.5, 100.0];
+    assert!(costs.iter().all(|cost| cost_to_fitness(*cost) > 0.0));
+    assert!(costs.windows(2).all(|pair| cost_to_fitness(pair[0]) > cost_to_fitness(pair[1])));
+}
+
+#[test]
+fn species_capacity_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        species_capacity: Some(0.25),
+        ..Conf::default()
+    };
+    // identical individuals fill a species after the other
+    let mut genus = genus_with_genomes(8, &conf, |_| vec![false; GENOME_SIZE]);
+    assert_eq!(genus.species_count(), 4);
+    assert!(genus.iter_species().all(|species| species.len() == 2));
+
+    // full species still take individuals when there are too many species
+    let conf = Conf {
+        max_species: Some(2),
+        ..conf
+    };
+    genus.speciate(&conf, (0..8).map(|i| IndividualTest::empty(i, GENOME_SIZE)));
+    assert_eq!(genus.species_count(), 2);
+    assert_eq!(genus.count_individuals(), 8);
+}
+
+#[test]
+fn built_in_selection_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 4,
+        ..Conf::default()
+    };
+    let mut genus = full_genus(4, GENOME_SIZE, &conf);
+    let (mut rng1, mut rng2) = (rand::thread_rng(), rand::thread_rng());
+    let tournament = Tournament::new(2);
+    let mut generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |it| tournament.select(it, &mut rng1),
+            &mut |it| tournament.select_parents(it, &mut rng2),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    generated_individuals.evaluate(|individual| individual.evaluate());
+    let genus = genus.next_generation(&conf, generated_individuals, elitist_population_management);
+    assert_eq!(genus.count_individuals(), 4);
+}
+
+/// Tournament selection, mutation of one gene and counters of the calls
+struct CountingOperators {
+    rng: ThreadRng,
+    tournament: Tournament,
+    next_id: usize,
+    mutations: usize,
+}
+
+impl ReproductionOperators<IndividualTest, f32> for CountingOperators {
+    fn select<'s>(&mut self, individuals: Box<SpeciesIter<'s, IndividualTest, f32>>) -> &'s IndividualTest {
+        self.tournament.select(individuals, &mut self.rng)
+    }
+
+    fn select_parents<'s>(&mut self, individuals: Box<SpeciesIter<'s, IndividualTest, f32>>) -> (&'s IndividualTest, &'s IndividualTest) {
+        self.tournament.select_parents(individuals, &mut self.rng)
+    }
+
+    fn reproduce(&mut self, parent: &IndividualTest) -> IndividualTest {
+        let mut child = parent.clone();
+        child.id = self.next_id;
+        self.next_id += 1;
+        child
+    }
+
+    fn crossover(&mut self, parent1: &IndividualTest, parent2: &IndividualTest) -> IndividualTest {
+        let child = parent1.crossover(parent2, self.next_id, &mut self.rng);
+        self.next_id += 1;
+        child
+    }
+
+    fn mutate(&mut self, individual: &mut IndividualTest) {
+        individual.mutate(&mut self.rng);
+        self.mutations += 1;
+    }
+}
+
+#[test]
+fn reproduction_operators_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 6,
+        ..Conf::default()
+    };
+    let mut genus = full_genus(6, GENOME_SIZE, &conf);
+    let mut operators = CountingOperators {
+        rng: rand::thread_rng(),
+        tournament: Tournament::new(2),
+        next_id: 100,
+        mutations: 0,
+    };
+    let mut generated_individuals = genus.update(&conf).generate_new_individuals_with(&conf, &mut operators);
+    assert_eq!(generated_individuals.need_evaluation.len(), 6);
+    assert_eq!(operators.mutations, 6);
+    assert_eq!(operators.next_id, 106);
+
+    generated_individuals.evaluate(|individual| individual.evaluate());
+    let genus = genus.next_generation(&conf, generated_individuals, elitist_population_management);
+    assert_eq!(genus.count_individuals(), 6);
+}
+
+#[test]
+fn batch_selection_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 6,
+        crossover_probability: 1.0,
+        ..Conf::default()
+    };
+    let mut genus = full_genus(6, GENOME_SIZE, &conf);
+    let mut rng = StdRng::seed_from_u64(0);
+    let batches: RefCell<Vec<Vec<usize>>> = RefCell::new(Vec::new());
+    let crossovers: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+    let generated_individuals = genus.update(&conf)
+        .generate_new_individuals_with_batch_selection(
+            &conf,
+            &mut |it, amount| {
+                let batch = StochasticUniversalSampling.select_batch(it, amount, &mut rng);
+                batches.borrow_mut().push(batch.iter().map(|individual| individual.id).collect());
+                batch
+            },
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, parent2: &IndividualTest| {
+                crossovers.borrow_mut().extend([parent1.id, parent2.id]);
+                parent1.clone()
+            },
+            &mut |_: &mut IndividualTest| {},
+        );
+    assert_eq!(generated_individuals.need_evaluation.len(), 6);
+    // one batch with the two parents of every offspring, used in order
+    assert_eq!(batches.borrow().len(), 1);
+    assert_eq!(batches.borrow()[0], *crossovers.borrow());
+    assert_eq!(crossovers.borrow().len(), 12);
+}
+
+/// Individual borrowing its target from the caller, so not `'static`
+#[derive(Clone, Debug)]
+struct BorrowingIndividual<'t> {
+    target: &'t [bool],
+    genome: Vec<bool>,
+    fitness: Option<f32>,
+}
+
+impl Individual<f32> for BorrowingIndividual<'_> {
+    fn fitness(&self) -> Option<f32> {
+        self.fitness
+    }
+
+    fn is_compatible(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn kernel_fitness_sharing_without_distance_test() {
+    let conf = Conf {
+        total_population_size: 4,
+        fitness_sharing: FitnessSharing::Kernel { sigma: 2.0, alpha: 1.0 },
+        ..Conf::default()
+    };
+    let target = vec![true; 6];
+    let mut genus: Genus<BorrowingIndividual, f32> = Genus::new();
+    genus.speciate(&conf, (0..4).map(|_| BorrowingIndividual { target: &target, genome: vec![false; 6], fitness: Some(1.0) }));
+    assert_eq!(genus.try_update(&conf).err(), Some("FitnessSharing::Kernel requires Individual::distance".to_string()));
+    // the genus was not modified
+    assert_eq!(genus.generations_without_improvement(), 0);
+    assert!(genus.iter_species().next().unwrap().iter_with_adjusted_fitness().all(|(_, adjusted)| adjusted.is_none()));
+}
+
+#[test]
+fn non_static_operators_test() {
+    let target = vec![true; 6];
+    let conf = Conf {
+        total_population_size: 4,
+        ..Conf::default()
+    };
+    let initial_population = (0..4).map(|_| BorrowingIndividual { target: &target, genome: vec![false; 6], fitness: None });
+    let mut genus: Genus<BorrowingIndividual, f32> = Genus::new();
+    genus.speciate(&conf, initial_population);
+    let mut evaluate = |individual: &mut BorrowingIndividual| {
+        let fitness = individual.genome.iter().zip(individual.target).filter(|(g, t)| g == t).count() as f32;
+        individual.fitness = Some(fitness);
+        fitness
+    };
+    genus.ensure_evaluated_population(&mut evaluate);
+
+    // the operators capture local state mutably
+    let mut rng = rand::thread_rng();
+    let mut mutated: Vec<usize> = Vec::new();
+    let mut generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &BorrowingIndividual| parent.clone(),
+            &mut |parent1: &BorrowingIndividual, _parent2: &BorrowingIndividual| parent1.clone(),
+            &mut |individual: &mut BorrowingIndividual| {
+                let gene = rng.gen_range(0..individual.genome.len());
+                individual.genome[gene] = true;
+                individual.fitness = None;
+                mutated.push(gene);
+            },
+        );
+    assert_eq!(mutated.len(), 4);
+    generated_individuals.evaluate(&mut evaluate);
+    let genus = genus.next_generation(&conf, generated_individuals, |new_individuals, _, _| new_individuals);
+    assert!(genus.iter_individuals().all(|individual| individual.fitness == Some(1.0)));
+}
+
+/// Genomes of the population after a few generations using only random generators derived from `seed`
+fn seeded_run(seed: u64) -> Vec<Vec<bool>> {
+    let conf = Conf {
+        total_population_size: 12,
+        crossover_probability: 1.0,
+        interspecies_mating_rate: 0.5,
+        representative_policy: RepresentativePolicy::Random,
+        offspring_rounding: OffspringRounding::StochasticRemainder,
+        ..Conf::default()
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    let initial_population = (0..12).map(|_| <IndividualTest as Genome<f32>>::random(&mut rng));
+    let mut genus: Genus<IndividualTest, f32> = Genus::with_seed(seed);
+    genus.speciate(&conf, initial_population);
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+
+    for _ in 0..3 {
+        let rng = RefCell::new(genus.fork_rng());
+        let tournament = Tournament::new(2);
+        let mut generated_individuals = genus.update(&conf)
+            .generate_new_individuals(
+                &conf,
+                &mut |it| tournament.select(it, &mut *rng.borrow_mut()),
+                &mut |it| tournament.select_parents(it, &mut *rng.borrow_mut()),
+                &mut |parent: &IndividualTest| parent.reproduce(&mut *rng.borrow_mut()),
+                &mut |parent1: &IndividualTest, parent2: &IndividualTest| Genome::<f32>::crossover(parent1, parent2, &mut *rng.borrow_mut()),
+                &mut |individual: &mut IndividualTest| Genome::<f32>::mutate(individual, &mut *rng.borrow_mut()),
+            );
+        generated_individuals.evaluate(|individual| individual.evaluate());
+        genus = genus.next_generation(&conf, generated_individuals, elitist_population_management);
+    }
+    genus.iter_individuals().map(|individual| individual.genome.clone()).collect()
+}
+
+#[test]
+fn seeded_rng_test() {
+    assert_eq!(seeded_run(42), seeded_run(42));
+    assert_ne!(seeded_run(42), seeded_run(43));
+}
+
+#[test]
+fn crossover_probability_test() {
+    const GENOME_SIZE: usize = 10;
+    let count_operators = |crossover_probability: f64| -> (usize, usize) {
+        let conf = Conf {
+            total_population_size: 40,
+            crossover_probability,
+            ..Conf::default()
+        };
+        let mut genus = full_genus(40, GENOME_SIZE, &conf);
+        let (reproductions, crossovers) = (Cell::new(0), Cell::new(0));
+        genus.update(&conf)
+            .generate_new_individuals(
+                &conf,
+                &mut |mut it| it.next().unwrap(),
+                &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+                &mut |parent: &IndividualTest| {
+                    reproductions.set(reproductions.get() + 1);
+                    parent.clone()
+                },
+                &mut |parent1: &IndividualTest, _parent2: &IndividualTest| {
+                    crossovers.set(crossovers.get() + 1);
+                    parent1.clone()
+                },
+                &mut |_individual: &mut IndividualTest| {},
+            );
+        (reproductions.get(), crossovers.get())
+    };
+
+    assert_eq!(count_operators(1.0), (0, 40));
+    assert_eq!(count_operators(0.0), (40, 0));
+    let (reproductions, crossovers) = count_operators(0.5);
+    assert_eq!(reproductions + crossovers, 40);
+    assert!(reproductions > 0 && crossovers > 0);
+}
+
+#[test]
+fn crossover_probability_validation_test() {
+    let conf = |crossover_probability: f64| Conf {
+        crossover_probability,
+        ..Conf::default()
+    };
+    assert!(conf(0.0).validate().is_ok());
+    assert!(conf(1.0).validate().is_ok());
+    assert!(conf(-0.1).validate().is_err());
+    assert!(conf(1.5).validate().is_err());
+    assert!(conf(f64::NAN).validate().is_err());
+    assert!(Conf { interspecies_mating_rate: 2.0, ..Conf::default() }.validate().is_err());
+
+    let mut genus = full_genus(10, 10, &conf(0.5));
+    assert!(genus.try_update(&conf(f64::NAN)).is_err());
+    assert!(genus.try_update(&conf(0.5)).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "crossover_probability")]
+fn crossover_probability_new_panics_test() {
+    Conf::new(10, f64::NAN, 10, 40, 15, 1.1, 0.9);
+}
+
+#[test]
+fn mutation_schedule_test() {
+    assert_eq!(MutationSchedule::Constant.strength(7), 1.0);
+
+    let linear = MutationSchedule::LinearDecay { initial: 1.0, last: 0.2, generations: 4 };
+    assert_eq!(linear.strength(0), 1.0);
+    assert!((linear.strength(2) - 0.6).abs() < 1e-9);
+    assert_eq!(linear.strength(4), 0.2);
+    assert_eq!(linear.strength(100), 0.2);
+
+    let exponential = MutationSchedule::ExponentialDecay { initial: 1.0, rate: 0.5, min: 0.1 };
+    assert_eq!(exponential.strength(1), 0.5);
+    assert_eq!(exponential.strength(2), 0.25);
+    assert_eq!(exponential.strength(10), 0.1);
+
+    let cyclical = MutationSchedule::Cyclical { min: 0.0, max: 1.0, period: 4 };
+    assert!((cyclical.strength(0) - 1.0).abs() < 1e-9);
+    assert!(cyclical.strength(2).abs() < 1e-9);
+    assert!((cyclical.strength(4) - 1.0).abs() < 1e-9);
+
+    // the genus follows the schedule along the generations
+    let conf = Conf {
+        total_population_size: 4,
+        mutation_schedule: exponential,
+        ..Conf::default()
+    };
+    let id_counter = Cell::new(100);
+    let mut genus = full_genus(4, 10, &conf);
+    assert_eq!(genus.mutation_strength(&conf), 1.0);
+    let mut genus = run_generation(&mut genus, &conf, &conf, &id_counter);
+    assert_eq!(genus.mutation_strength(&conf), 0.5);
+    let genus = run_generation(&mut genus, &conf, &conf, &id_counter);
+    assert_eq!(genus.mutation_strength(&conf), 0.25);
+}
+
+#[test]
+fn restored_generation_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        mutation_schedule: MutationSchedule::ExponentialDecay { initial: 1.0, rate: 0.5, min: 0.1 },
+        // from a temperature that flattens the fitness differences to one that amplifies them
+        adjusted_fitness_mode: AdjustedFitnessMode::Boltzmann { initial_temperature: 10_000_000.0, cooling_rate: 0.0001, min_temperature: 1.0 },
+        ..asexual_conf(6)
+    };
+    // two species, one with an outlier fitness
+    let restored_genus = |generation| {
+        let mut genus: Genus<IndividualTest, f32> = Genus::new();
+        genus.set_generation(generation);
+        genus.speciate(&conf, (0..6).map(|i| {
+            let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+            individual.genome = vec![i % 2 == 0; GENOME_SIZE];
+            individual
+        }));
+        genus.ensure_evaluated_population(|individual| {
+            let fitness = if individual.genome[0] { 1_000_000.0 } else { 1.0 };
+            individual.fitness = Some(fitness);
+            fitness
+        });
+        genus
+    };
+    let weak_offspring = |genus: &mut Genus<IndividualTest, f32>| selectable_fitnesses(genus, &conf).into_iter()
+        .filter(|fitness| *fitness == 1.0)
+        .count();
+
+    let mut genus = restored_genus(0);
+    assert_eq!(weak_offspring(&mut genus), 2 * 3);
+    let mut genus = restored_genus(2);
+    assert_eq!(genus.generation(), 2);
+    assert_eq!(genus.mutation_strength(&conf), 0.25);
+    assert_eq!(weak_offspring(&mut genus), 0);
+
+    // the schedule continues from the restored generation
+    let id_counter = Cell::new(100);
+    let genus = run_generation(&mut genus, &conf, &conf, &id_counter);
+    assert_eq!(genus.generation(), 3);
+    assert_eq!(genus.mutation_strength(&conf), 0.125);
+}
+
+#[test]
+fn batch_offspring_test() {
+    const GENOME_SIZE: usize = 10;
+    for (population_size, expected_crossovers) in [(6, 3), (5, 3)] {
+        let conf = Conf {
+            total_population_size: population_size,
+            ..Conf::default()
+        };
+        let mut genus = full_genus(population_size, GENOME_SIZE, &conf);
+        let crossovers = Cell::new(0);
+        let mut generated_individuals = genus.update(&conf)
+            .generate_new_individuals_batch(
+                &conf,
+                &mut |mut it| it.next().unwrap(),
+                &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+                &mut |parent: &IndividualTest| vec![parent.clone()],
+                // two children per crossover, the extra one of the last crossover is discarded
+                &mut |parent1: &IndividualTest, parent2: &IndividualTest| {
+                    crossovers.set(crossovers.get() + 1);
+                    vec![parent1.clone(), parent2.clone()]
+                },
+                &mut |individual: &mut IndividualTest| individual.fitness = None,
+            );
+        assert_eq!(crossovers.get(), expected_crossovers);
+        assert_eq!(generated_individuals.need_evaluation.len(), population_size);
+        assert!(generated_individuals.need_evaluation.iter().all(|individual| individual.borrow().fitness.is_none()));
+
+        generated_individuals.evaluate(|individual| individual.evaluate());
+        let genus = genus.next_generation(&conf, generated_individuals, elitist_population_management);
+        assert_eq!(genus.count_individuals(), population_size);
+    }
+}
+
+#[test]
+fn fallible_operators_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        operator_retries: 3,
+        ..asexual_conf(6)
+    };
+    let mut genus = full_genus(6, GENOME_SIZE, &conf);
+
+    // every reproduction succeeds at the third attempt
+    let calls = Cell::new(0);
+    let generated_individuals = genus.update(&conf)
+        .generate_new_individuals_fallible(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| {
+                calls.set(calls.get() + 1);
+                if calls.get() % 3 == 0 { Ok(parent.clone()) } else { Err(OperatorError::new("unlucky")) }
+            },
+            &mut |_parent1: &IndividualTest, _parent2: &IndividualTest| Err(OperatorError::new("unused")),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    assert_eq!(generated_individuals.need_evaluation.len(), 6);
+    assert_eq!(generated_individuals.operator_failures, 12);
+    assert_eq!(generated_individuals.operator_fallbacks, 0);
+
+    // the crossover always fails, the offspring are clones of the first parent
+    let conf = Conf {
+        crossover_probability: 1.0,
+        operator_retries: 1,
+        ..conf
+    };
+    let generated_individuals = genus.update(&conf)
+        .generate_new_individuals_fallible(
+            &conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| Ok(parent.clone()),
+            &mut |_parent1: &IndividualTest, _parent2: &IndividualTest| Err(OperatorError::new("incompatible")),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    assert_eq!(generated_individuals.operator_failures, 12);
+    assert_eq!(generated_individuals.operator_fallbacks, 6);
+    assert!(generated_individuals.need_evaluation.iter().all(|individual| individual.borrow().id == 0));
+}
+
+#[test]
+fn adjusted_fitness_selection_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        total_population_size: 6,
+        ..Conf::default()
+    };
+    let mut genus = full_genus(6, GENOME_SIZE, &conf);
+    // same species, but the first individual has no adjusted fitness to share
+    genus.iter_individuals_mut().for_each(|individual| if individual.id == 0 { individual.fitness = Some(0.0) });
+
+    let mut rng = rand::thread_rng();
+    let rng = RefCell::new(&mut rng);
+    let selected: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+    let generated_individuals = genus.update(&conf)
+        .generate_new_individuals(
+            &conf,
+            &mut |it| {
+                let parent = AdjustedFitnessProportional.select(*it, &mut **rng.borrow_mut());
+                selected.borrow_mut().push(parent.id);
+                parent
+            },
+            &mut |it| {
+                let (parent1, parent2) = AdjustedFitnessProportional.select_parents(*it, &mut **rng.borrow_mut());
+                selected.borrow_mut().extend([parent1.id, parent2.id]);
+                (parent1, parent2)
+            },
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        );
+    assert_eq!(generated_individuals.need_evaluation.len(), 6);
+    assert_eq!(selected.borrow().len(), 12);
+    assert!(!selected.borrow().contains(&0));
+}
+
+#[test]
+fn stale_species_lock_test() {
+    let conf = Conf {
+        total_population_size: 4,
+        ..Conf::default()
+    };
+    let id_counter = Cell::new(100);
+    let mut genus = full_genus(4, 10, &conf);
+    assert!(genus.lock_species(7));
+    let genus = run_generation(&mut genus, &conf, &conf, &id_counter);
+    assert!(!genus.is_species_locked(7));
+    assert!(genus.locked_species().is_empty());
+}
+
+#[test]
+fn activation_values_test() {
+    let expected: [(Activation, [f64; 3]); 12] = [
+        (Activation::Identity, [-2.0, 0.0, 2.0]),
+        (Activation::Sigmoid, [0.11920292202211755, 0.5, 0.8807970779778823]),
+        (Activation::Tanh, [-0.9640275800758169, 0.0, 0.9640275800758169]),
+        (Activation::Relu, [0.0, 0.0, 2.0]),
+        (Activation::Sin, [-0.9092974268256817, 0.0, 0.9092974268256817]),
+        (Activation::Gauss, [0.01831563888873418, 1.0, 0.01831563888873418]),
+        (Activation::Abs, [2.0, 0.0, 2.0]),
+        (Activation::Step, [0.0, 0.0, 1.0]),
+        (Activation::Softplus, [0.1269280110429725, std::f64::consts::LN_2, 2.1269280110429727]),
+        (Activation::Clamped, [-1.0, 0.0, 1.0]),
+        (Activation::Square, [4.0, 0.0, 4.0]),
+        (Activation::Cube, [-8.0, 0.0, 8.0]),
+    ];
+    for (activation, values) in expected {
+        for (x, value) in [-2.0, 0.0, 2.0].iter().zip(values) {
+            assert!((activation.activate(*x) - value).abs() < 1e-12, "{}({}) = {}", activation, x, activation.activate(*x));
+        }
+    }
+    // no overflow for large inputs
+    assert_eq!(Activation::Softplus.activate(1000.0f32), 1000.0);
+    assert_eq!(Activation::Sigmoid.activate(-1000.0f32), 0.0);
+}
+
+#[test]
+fn activation_names_test() {
+    for activation in Activation::ALL {
+        assert_eq!(activation.name().parse::<Activation>(), Ok(activation));
+        assert_eq!(activation.to_string(), activation.name());
+    }
+    assert!("linear".parse::<Activation>().is_err());
+}
+
+/// Offspring (clones of their parents) of `genus` with their trial budgets
+fn trial_offspring(genus: &mut Genus<IndividualTest, f32>, conf: &Conf) -> GenusSeed<IndividualTest, f32> {
+    genus.update(conf)
+        .generate_new_individuals(
+            conf,
+            &mut |mut it| it.next().unwrap(),
+            &mut |mut it| (it.next().unwrap(), it.next().unwrap()),
+            &mut |parent: &IndividualTest| parent.clone(),
+            &mut |parent1: &IndividualTest, _parent2: &IndividualTest| parent1.clone(),
+            &mut |_individual: &mut IndividualTest| {},
+        )
+}
+
+/// Trial budgets of the offspring of the two species of `two_species_genus`: (all genes set, half genes set)
+fn species_trial_budgets(generated_individuals: &GenusSeed<IndividualTest, f32>) -> (HashSet<usize>, HashSet<usize>) {
+    let mut budgets = (HashSet::new(), HashSet::new());
+    for (individual, &trials) in generated_individuals.need_evaluation.iter().zip(generated_individuals.trial_budgets.iter()) {
+        if individual.borrow().genome.iter().all(|gene| *gene) {
+            budgets.0.insert(trials);
+        } else {
+            budgets.1.insert(trials);
+        }
+    }
+    budgets
+}
+
+/// Genus with a species of individuals with all the genes set and one with half of them set
+fn two_species_genus(conf: &Conf) -> Genus<IndividualTest, f32> {
+    const GENOME_SIZE: usize = 10;
+    let mut genus = genus_with_genomes(4, conf, |i| (0..GENOME_SIZE).map(|gene| i % 2 == 0 || gene < GENOME_SIZE / 2).collect());
+    genus.ensure_evaluated_population(|individual| individual.evaluate());
+    assert_eq!(genus.species_count(), 2);
+    genus
+}
+
+#[test]
+fn trial_budget_species_rank_test() {
+    let conf = Conf {
+        trial_budget: TrialBudget::SpeciesRank { min: 1, max: 5 },
+        ..asexual_conf(4)
+    };
+    let mut genus = two_species_genus(&conf);
+    let mut generated_individuals = trial_offspring(&mut genus, &conf);
+    assert_eq!(species_trial_budgets(&generated_individuals), (HashSet::from([5]), HashSet::from([1])));
+
+    // trials 0, 2, 4, ...
+    generated_individuals.evaluate_trials(|individual, trials| {
+        let samples: Vec<f32> = (0..trials).map(|trial| 2.0 * trial as f32).collect();
+        individual.fitness = Some(samples.iter().sum::<f32>() / trials as f32);
+        samples
+    });
+    for report in &generated_individuals.trial_reports {
+        match report.trials {
+            5 => assert_eq!((report.mean, report.variance), (4.0, 8.0)),
+            1 => assert_eq!((report.mean, report.variance), (0.0, 0.0)),
+            trials => panic!("unexpected trial budget {}", trials),
+        }
+    }
+}
+
+#[test]
+fn trial_budget_species_stagnation_test() {
+    const GENOME_SIZE: usize = 10;
+    let conf = Conf {
+        species_max_stagnation: 4,
+        trial_budget: TrialBudget::SpeciesStagnation { min: 1, max: 5 },
+        ..asexual_conf(4)
+    };
+    let mut genus = two_species_genus(&conf);
+    assert_eq!(species_trial_budgets(&trial_offspring(&mut genus, &conf)), (HashSet::from([5]), HashSet::from([5])));
+    // the half species gets worse, it stagnates for a generation
+    let mut genus = replace_generation(&mut genus, &conf, |individual| {
+        if let Some(last_set) = individual.genome.iter().rposition(|gene| *gene).filter(|&gene| gene < GENOME_SIZE - 1) {
+            individual.genome[last_set] = false;
+        }
+    });
+    assert_eq!(species_trial_budgets(&trial_offspring(&mut genus, &conf)), (HashSet::from([5]), HashSet::from([4])));
+}
+
+#[test]
+fn species_novelty_bonus_test() {
+    const GENOME_SIZE: usize = 12;
+    let conf = |species_novelty_bonus| Conf {
+        total_population_size: 6,
+        species_novelty_bonus,
+        species_novelty_distance: 6.0,
+        ..Conf::default()
+    };
+    // mean adjusted fitness of the species, by number of genes set of their individuals
+    let adjusted_fitnesses = |set_genes: &[usize], conf: &Conf| {
+        let mut genus: Genus<IndividualTest, f32> = Genus::new();
+        genus.speciate(conf, set_genes.iter().flat_map(|&set| [set, set]).enumerate().map(|(i, set)| {
+            let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+            individual.genome = (0..GENOME_SIZE).map(|gene| gene < set).collect();
+            individual
+        }));
+        genus.ensure_evaluated_population(|individual| individual.evaluate());
+        assert_eq!(genus.species_count(), set_genes.len());
+        genus.update(conf);
+        set_genes.iter()
+            .map(|&set| genus.iter_species()
+                .find(|species| species.representative().unwrap().genome.iter().filter(|gene| **gene).count() == set)
+                .unwrap()
+                .mean_adjusted_fitness().unwrap())
+            .collect::<Vec<f32>>()
+    };
+    let bonus_factors = |set_genes: &[usize]| -> Vec<f32> {
+        adjusted_fitnesses(set_genes, &conf(2.0)).iter()
+            .zip(adjusted_fitnesses(set_genes, &conf(1.0)))
+            .map(|(with_bonus, without_bonus)| with_bonus / without_bonus)
+            .collect()
+    };
+
+    // two distant species are both novel
+    assert_eq!(bonus_factors(&[12, 0]), vec![2.0, 2.0]);
+    // a third species between them, at distance 7 and 5: only the first one stays novel
+    assert_eq!(bonus_factors(&[12, 5, 0]), vec![2.0, 1.0, 1.0]);
+}
+
+#[test]
+fn boltzmann_scaling_test() {
+    const GENOME_SIZE: usize = 10;
+    let boltzmann = AdjustedFitnessMode::Boltzmann { initial_temperature: 8.0, cooling_rate: 0.5, min_temperature: 2.0 };
+    assert_eq!(boltzmann.temperature(0), Some(8.0));
+    assert_eq!(boltzmann.temperature(1), Some(4.0));
+    assert_eq!(boltzmann.temperature(2), Some(2.0));
+    // floored to min_temperature
+    assert_eq!(boltzmann.temperature(3), Some(2.0));
+    assert_eq!(boltzmann.temperature(usize::MAX), Some(2.0));
+    assert_eq!(AdjustedFitnessMode::Raw.temperature(0), None);
+
+    // negative fitness is fine, the values are relative to the best fitness
+    let conf = Conf {
+        total_population_size: 4,
+        young_age_fitness_boost: 1.0,
+        adjusted_fitness_mode: boltzmann,
+        ..Conf::default()
+    };
+    let mut genus: Genus<IndividualTest, f32> = Genus::new();
+    genus.set_generation(2);
+    genus.speciate(&conf, (0..4).map(|i| {
+        let mut individual = IndividualTest::empty(i, GENOME_SIZE);
+        individual.genome = vec![i % 2 == 0; GENOME_SIZE];
+        individual
+    }));
+    genus.ensure_evaluated_population(|individual| {
+        let fitness = if individual.genome[0] { -1.0 } else { -3.0 };
+        individual.fitness = Some(fitness);
+        fitness
+    });
+    genus.update(&conf);
+    let mean_adjusted_fitness = |best: bool| genus.iter_species()
+        .find(|species| species.representative().unwrap().genome[0] == best)
+        .unwrap()
+        .mean_adjusted_fitness().unwrap();
+    // exp((fitness - best) / temperature) shared by the 2 individuals of the species
+    assert_eq!(mean_adjusted_fitness(true), 0.5);
+    assert!((mean_adjusted_fitness(false) - (-1.0f32).exp() / 2.0).abs() < 1e-6);
 }
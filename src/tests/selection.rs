@@ -0,0 +1,192 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::selection::{roulette_index, sus_indices, DistinctParents, ExponentialRank, Lexicase, LinearRank, Roulette, Selector, StochasticUniversalSampling, Tournament, Truncation};
+use crate::speciation::{Individual, Minimize};
+use crate::tests::IndividualTest;
+
+/// Individuals with fitness equal to their id
+fn individuals(n: usize) -> Vec<IndividualTest> {
+    (0..n).map(|id| IndividualTest::with_fitness(id, Some(id as f32))).collect()
+}
+
+#[test]
+fn tournament() {
+    let individuals = individuals(5);
+    let mut rng = StdRng::seed_from_u64(0);
+    // the whole population takes part, the best always wins
+    let full = Tournament::new(5);
+    for _ in 0..10 {
+        assert_eq!(full.select(individuals.iter(), &mut rng).id, 4);
+    }
+    let (a, b) = full.select_parents(individuals.iter(), &mut rng);
+    assert_eq!((a.id, b.id), (4, 4));
+
+    // the worst can never win a tournament of two without replacement
+    let pair = Tournament::new(2);
+    assert!((0..100).all(|_| pair.select(individuals.iter(), &mut rng).id != 0));
+    let with_replacement = Tournament { size: 2, replacement: true };
+    assert!((0..200).any(|_| with_replacement.select(individuals.iter(), &mut rng).id == 0));
+}
+
+#[test]
+fn distinct_parents() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let individuals = individuals(3);
+    // the best always wins the full tournament, the second parent is the best of the others
+    let distinct = DistinctParents(Tournament::new(3));
+    for _ in 0..10 {
+        let (a, b) = distinct.select_parents(individuals.iter(), &mut rng);
+        assert_eq!((a.id, b.id), (2, 1));
+    }
+    let (a, b) = DistinctParents(Roulette).select_parents(individuals.iter().take(1), &mut rng);
+    assert_eq!((a.id, b.id), (0, 0));
+}
+
+#[test]
+fn roulette() {
+    let mut rng = StdRng::seed_from_u64(0);
+    // zero weights are never chosen
+    assert!((0..100).all(|_| roulette_index(&[0.0, 1.0, 0.0, 3.0], &mut rng) % 2 == 1));
+    let threes = (0..1000).filter(|_| roulette_index(&[0.0, 1.0, 0.0, 3.0], &mut rng) == 3).count();
+    assert!(threes > 650 && threes < 850);
+    // uniform without positive weights
+    assert!((0..100).any(|_| roulette_index(&[0.0, -1.0], &mut rng) == 1));
+    assert!((0..100).any(|_| roulette_index(&[0.0f32, 0.0], &mut rng) == 0));
+
+    let individuals = individuals(3);
+    assert!((0..100).all(|_| Roulette.select(individuals.iter(), &mut rng).id != 0));
+    let adjusted = individuals.iter().map(|individual| (individual, if individual.id == 0 { 1.0 } else { 0.0 }));
+    assert_eq!(Roulette.select_weighted(adjusted, &mut rng).id, 0);
+}
+
+#[test]
+fn stochastic_universal_sampling() {
+    let mut rng = StdRng::seed_from_u64(0);
+    // every index is chosen as many times as expected (within one)
+    for _ in 0..20 {
+        let indices = sus_indices(&[1.0, 0.0, 2.0, 1.0], 8, &mut rng);
+        assert_eq!(indices.len(), 8);
+        assert_eq!(indices.iter().filter(|i| **i == 0).count(), 2);
+        assert_eq!(indices.iter().filter(|i| **i == 1).count(), 0);
+        assert_eq!(indices.iter().filter(|i| **i == 2).count(), 4);
+    }
+    assert_eq!(sus_indices(&[0.0f32; 3], 3, &mut rng), vec![0, 1, 2]);
+
+    let individuals = individuals(3);
+    let batches: Vec<Vec<usize>> = (0..20)
+        .map(|_| StochasticUniversalSampling.select_batch(individuals.iter(), 3, &mut rng).iter()
+            .map(|individual| individual.id)
+            .collect())
+        .collect();
+    for batch in &batches {
+        let mut sorted = batch.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec![1, 2, 2]);
+    }
+    // shuffled, not in the order of the candidates
+    assert!(batches.iter().any(|batch| batch[0] == 2));
+
+    let parents: Vec<(usize, usize)> = (0..20)
+        .map(|_| StochasticUniversalSampling.select_parents(individuals.iter(), &mut rng))
+        .map(|(a, b)| (a.id, b.id))
+        .collect();
+    assert!(parents.iter().all(|&(a, b)| a != 0 && b != 0 && (a == 2 || b == 2)));
+    assert!(parents.iter().any(|&(a, _)| a == 2) && parents.iter().any(|&(_, b)| b == 2));
+}
+
+#[test]
+fn rank_selection() {
+    let mut rng = StdRng::seed_from_u64(0);
+    // badly scaled fitness: the ranks matter, not the values
+    let mut individuals = individuals(3);
+    individuals[2].fitness = Some(1.0e9);
+
+    let linear = LinearRank { selection_pressure: 2.0 };
+    assert!((0..100).all(|_| linear.select(individuals.iter(), &mut rng).id != 0));
+    assert!((0..100).any(|_| linear.select(individuals.iter(), &mut rng).id == 1));
+    let uniform = LinearRank { selection_pressure: 1.0 };
+    assert!((0..100).any(|_| uniform.select(individuals.iter(), &mut rng).id == 0));
+
+    let exponential = ExponentialRank { base: 0.5 };
+    let best = (0..1000).filter(|_| exponential.select(individuals.iter(), &mut rng).id == 2).count();
+    // weights 1, 0.5, 0.25
+    assert!(best > 500 && best < 640);
+}
+
+#[test]
+fn truncation() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let individuals = individuals(10);
+    // top 25% of 10, rounded up: the 3 best
+    let truncation = Truncation { fraction: 0.25 };
+    let chosen: Vec<usize> = (0..100).map(|_| truncation.select(individuals.iter(), &mut rng).id).collect();
+    assert!(chosen.iter().all(|id| *id >= 7));
+    assert!((7..10).all(|id| chosen.contains(&id)));
+    // at least the best is eligible
+    let best = Truncation { fraction: 0.0 };
+    assert_eq!(best.select(individuals.iter(), &mut rng).id, 9);
+}
+
+#[derive(Clone)]
+struct CaseIndividual {
+    id: usize,
+    cases: Vec<f32>,
+}
+
+impl Individual<f32> for CaseIndividual {
+    fn fitness(&self) -> Option<f32> {
+        Some(self.cases.iter().sum())
+    }
+
+    fn is_compatible(&self, _other: &Self) -> bool {
+        true
+    }
+
+    fn case_scores(&self) -> Option<&[f32]> {
+        Some(&self.cases)
+    }
+}
+
+#[test]
+fn lexicase() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let individuals = [
+        // specialists, each the best on one case
+        CaseIndividual { id: 0, cases: vec![1.0, 0.0, 0.0] },
+        CaseIndividual { id: 1, cases: vec![0.0, 1.0, 0.0] },
+        CaseIndividual { id: 2, cases: vec![0.0, 0.0, 1.0] },
+        // best average, but never the best on a case
+        CaseIndividual { id: 3, cases: vec![0.9, 0.9, 0.9] },
+    ];
+    let exact = Lexicase::default();
+    let chosen: Vec<usize> = (0..100).map(|_| exact.select(individuals.iter(), &mut rng).id).collect();
+    assert!(!chosen.contains(&3));
+    assert!((0..3).all(|id| chosen.contains(&id)));
+
+    // within the tolerance the generalist passes every case
+    let tolerant = Lexicase { epsilon: 0.2 };
+    assert!((0..100).all(|_| tolerant.select(individuals.iter(), &mut rng).id == 3));
+
+    // the case scores go through the minimization wrapper unchanged
+    let minimized: Vec<Minimize<CaseIndividual>> = individuals.iter().cloned().map(Minimize).collect();
+    assert_eq!(minimized[3].case_scores(), Some(&[0.9, 0.9, 0.9][..]));
+    assert!((0..100).all(|_| tolerant.select(minimized.iter(), &mut rng).0.id == 3));
+}
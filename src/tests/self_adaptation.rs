@@ -0,0 +1,53 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::genome::self_adaptation::{standard_normal, StrategyParameters};
+
+#[test]
+fn standard_normal_moments() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let samples: Vec<f64> = (0..10000).map(|_| standard_normal(&mut rng)).collect();
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    let variance = samples.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / samples.len() as f64;
+    assert!(mean.abs() < 0.05);
+    assert!((variance - 1.0).abs() < 0.05);
+}
+
+#[test]
+fn self_adaptation() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut genes = vec![0.0; 4];
+    let mut parameters = StrategyParameters::new(4, 0.5);
+    parameters.mutate(&mut genes, &mut rng);
+    assert!(genes.iter().all(|gene| *gene != 0.0));
+    assert!(parameters.sigmas.iter().all(|sigma| *sigma != 0.5));
+
+    // the step sizes never fall below the minimum
+    parameters.min_sigma = 0.1;
+    parameters.sigmas = vec![0.1; 4];
+    for _ in 0..100 {
+        parameters.mutate_sigmas(&mut rng);
+        assert!(parameters.sigmas.iter().all(|sigma| *sigma >= 0.1));
+    }
+
+    let other = StrategyParameters { sigmas: vec![0.25, 0.5, 0.75, 1.0], min_sigma: 0.1 };
+    let child = StrategyParameters { sigmas: vec![0.25; 4], min_sigma: 0.1 }.recombine(&other);
+    assert_eq!(child.sigmas, vec![0.25, 0.375, 0.5, 0.625]);
+}
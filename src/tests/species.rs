@@ -0,0 +1,136 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use rand::thread_rng;
+
+use crate::speciation::{Conf, RepresentativePolicy, Species};
+use crate::tests::IndividualTest;
+
+#[test]
+fn fitness_statistics() {
+    let individual = IndividualTest::with_fitness;
+    let mut species = Species::new(individual(0, None), 1);
+    assert_eq!(species.mean_fitness(), None);
+    assert_eq!(species.median_fitness(), None);
+    assert_eq!(species.mean_adjusted_fitness(), None);
+
+    for (id, fitness) in [2.0, 4.0, 4.0, 6.0].iter().enumerate() {
+        species.insert(individual(id + 1, Some(*fitness)));
+    }
+    assert_eq!(species.len(), 5);
+    assert_eq!(species.mean_fitness(), Some(4.0));
+    assert_eq!(species.median_fitness(), Some(4.0));
+    assert_eq!(species.std_fitness(), Some(2.0f32.sqrt()));
+    assert_eq!(species.min_fitness(), Some(2.0));
+    assert_eq!(species.max_fitness(), Some(6.0));
+}
+
+#[test]
+fn adjusted_fitness_access() {
+    let individual = IndividualTest::with_fitness(0, Some(2.0));
+    let mut species = Species::new(individual.clone(), 1);
+    species.insert(individual);
+    assert_eq!(species.adjusted_fitness(0), None);
+
+    let conf = Conf { young_age_fitness_boost: 1.0, ..Conf::default() };
+    species.compute_adjust_fitness(true, &|fitness| fitness, None, &conf);
+    // shared by the two individuals of the species
+    assert_eq!(species.adjusted_fitness(1), Some(1.0));
+    assert!(species.iter_with_adjusted_fitness().all(|(_, adjusted_fitness)| adjusted_fitness == Some(1.0)));
+    assert_eq!(species.mean_adjusted_fitness(), Some(1.0));
+
+    // the age adjustment function replaces the young boost
+    let conf = Conf {
+        age_fitness_adjustment: Some(Arc::new(|age, fitness| fitness * (age.generations + 3) as f64)),
+        ..Conf::default()
+    };
+    species.compute_adjust_fitness(true, &|fitness| fitness, None, &conf);
+    assert_eq!(species.adjusted_fitness(0), Some(3.0));
+}
+
+#[test]
+fn sorted_iteration() {
+    let individual = IndividualTest::with_fitness;
+    let mut species = Species::new(individual(0, Some(1.0)), 1);
+    species.insert(individual(1, None));
+    species.insert(individual(2, Some(3.0)));
+    let ids = |species: &Species<IndividualTest, f32>| species.iter_sorted().map(|i| i.id).collect::<Vec<_>>();
+    assert_eq!(ids(&species), vec![2, 0, 1]);
+
+    species.insert(individual(3, Some(2.0)));
+    assert_eq!(ids(&species), vec![2, 3, 0, 1]);
+    species.individual_mut(0).fitness = Some(5.0);
+    assert_eq!(ids(&species), vec![0, 2, 3, 1]);
+    // the stored order is untouched
+    assert_eq!(species.iter().map(|i| i.id).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn representative_policy_test() {
+    // genomes with 0, 1 and 5 leading genes set: the medoid is the second, the best is the third
+    let mut species: Species<IndividualTest, f32> = Species::new(IndividualTest::empty(0, 6), 1);
+    for (id, set_genes, fitness) in [(1, 1, 1.0), (2, 5, 3.0)] {
+        let mut individual = IndividualTest::with_set_genes(id, 6, set_genes);
+        individual.fitness = Some(fitness);
+        species.insert(individual);
+    }
+    let representative = |representative_policy| {
+        let conf = Conf { representative_policy, ..Conf::default() };
+        species.clone_with_new_individuals(std::iter::empty(), &conf, &mut thread_rng())
+            .promote()
+            .representative().unwrap().id
+    };
+    assert_eq!(representative(RepresentativePolicy::First), 0);
+    assert_eq!(representative(RepresentativePolicy::BestFitness), 2);
+    assert_eq!(representative(RepresentativePolicy::Medoid), 1);
+}
+
+#[test]
+fn age_fitness_adjustment_test() {
+    let individual = IndividualTest::with_fitness(0, Some(2.0));
+    let mut species: Species<IndividualTest, f32> = Species::new(individual.clone(), 1);
+    species.insert(individual);
+    let adjusted_fitness = |species: &mut Species<IndividualTest, f32>, conf: &Conf| {
+        species.compute_adjust_fitness(true, &|fitness| fitness, None, conf);
+        species.adjusted_fitness(0).unwrap()
+    };
+    let conf = Conf {
+        young_age_threshold: 2,
+        old_age_threshold: 2,
+        young_age_fitness_boost: 100.0,
+        old_age_fitness_penalty: 0.001,
+        ..Conf::default()
+    };
+    // shared by the 2 individuals
+    assert_eq!(adjusted_fitness(&mut species, &conf), 100.0);
+
+    // the closure replaces both the boost of the young and the penalty of the old species
+    let scale = 3.0;
+    let conf = Conf {
+        age_fitness_adjustment: Some(Arc::new(move |_age, fitness| fitness * scale)),
+        ..conf
+    };
+    assert!(format!("{:?}", conf).contains("age_fitness_adjustment: Some"));
+    assert_eq!(adjusted_fitness(&mut species, &conf), 3.0);
+    for _ in 0..3 {
+        species.increase_generations();
+    }
+    assert_eq!(adjusted_fitness(&mut species, &conf), 3.0);
+    assert_eq!(adjusted_fitness(&mut species, &Conf { age_fitness_adjustment: None, ..conf }), 0.001);
+}
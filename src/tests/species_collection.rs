@@ -0,0 +1,207 @@
+/*
+ * This file is part of the rustneat project.
+ * Copyright (c) 2021 Matteo De Carlo.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, version 3.
+ *
+ * This program is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashSet;
+
+use crate::speciation::species_collection::SpeciesCollection;
+use crate::speciation::{Conf, FitnessSharing, SpeciationStrategy, Species};
+use crate::tests::IndividualTest;
+
+/// Creates a collection where species `i` has id `i + 1` and one individual for every given fitness
+fn collection(species_fitnesses: &[&[f32]]) -> SpeciesCollection<IndividualTest, f32> {
+    let mut collection = SpeciesCollection::new();
+    for (i, fitnesses) in species_fitnesses.iter().enumerate() {
+        let mut species = Species::new(IndividualTest::with_fitness(0, Some(fitnesses[0])), i + 1);
+        for (id, fitness) in fitnesses.iter().enumerate().skip(1) {
+            species.insert(IndividualTest::with_fitness(id, Some(*fitness)));
+        }
+        collection.push(species);
+    }
+    collection
+}
+
+#[test]
+fn get_worst_by_best_fitness() {
+    let collection = collection(&[&[1.0, 9.0], &[3.0], &[5.0, 2.0]]);
+    let (worst_i, worst) = collection.get_worst(1, None).unwrap();
+    assert_eq!(worst_i, 1);
+    assert_eq!(worst.id, 2);
+}
+
+#[test]
+fn get_worst_minimal_size_and_exclusion() {
+    let collection = collection(&[&[1.0, 9.0], &[3.0], &[5.0, 2.0]]);
+    assert_eq!(collection.get_worst(2, None).unwrap().0, 2);
+
+    let excluded: HashSet<usize> = [3].iter().cloned().collect();
+    assert_eq!(collection.get_worst(2, Some(&excluded)).unwrap().0, 0);
+}
+
+#[test]
+fn get_worst_ties_return_first() {
+    let collection = collection(&[&[4.0], &[2.0, 1.0], &[2.0]]);
+    assert_eq!(collection.get_worst(1, None).unwrap().0, 1);
+
+    let excluded: HashSet<usize> = [2].iter().cloned().collect();
+    assert_eq!(collection.get_worst(1, Some(&excluded)).unwrap().0, 2);
+}
+
+#[test]
+fn get_worst_exhaustion() {
+    let collection = collection(&[&[1.0], &[3.0]]);
+    assert!(collection.get_worst(2, None).is_none());
+
+    let excluded: HashSet<usize> = [1, 2].iter().cloned().collect();
+    assert!(collection.get_worst(1, Some(&excluded)).is_none());
+}
+
+#[test]
+fn merge_compatible_species() {
+    // all the representatives have the same genome
+    let mut merged = collection(&[&[1.0], &[2.0, 3.0], &[4.0]]);
+    assert_eq!(merged.merge_compatible(&Conf::default(), &HashSet::new()), 2);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged.get(0).id, 1);
+    assert_eq!(merged.count_individuals(), 4);
+
+    let mut partially_merged = collection(&[&[1.0], &[2.0, 3.0], &[4.0]]);
+    let excluded: HashSet<usize> = [2].iter().cloned().collect();
+    assert_eq!(partially_merged.merge_compatible(&Conf::default(), &excluded), 1);
+    assert_eq!(partially_merged.len(), 2);
+    assert_eq!(partially_merged.get(1).id, 2);
+}
+
+#[test]
+fn remove_stagnant_species() {
+    let mut collection = collection(&[&[1.0], &[2.0], &[3.0], &[4.0]]);
+    for i in 0..3 {
+        collection.get_mut(i).increase_no_improvements_generations();
+    }
+    collection.get_mut(3).increase_no_improvements_generations();
+    collection.get_mut(3).increase_no_improvements_generations();
+
+    // species 4 is the best, species 1 is locked
+    let excluded: HashSet<usize> = [1].iter().cloned().collect();
+    assert_eq!(collection.remove_stagnant(1, 0, &excluded), 0);
+    assert_eq!(collection.remove_stagnant(0, 2, &excluded), 1);
+    let ids: Vec<usize> = collection.iter().map(|species| species.id).collect();
+    assert_eq!(ids, vec![1, 3, 4]);
+}
+
+#[test]
+fn silhouette_score() {
+    let individual = |id: usize, genes: usize| IndividualTest::with_set_genes(id, 6, genes);
+    let mut rng = rand::thread_rng();
+    let mut collection = SpeciesCollection::<IndividualTest, f32>::new();
+    assert_eq!(collection.silhouette(None, &mut rng), None);
+    let mut coherent = Species::new(individual(0, 0), 1);
+    coherent.insert(individual(1, 0));
+    collection.push(coherent);
+    let mut far = Species::new(individual(2, 6), 2);
+    far.insert(individual(3, 6));
+    collection.push(far);
+    assert_eq!(collection.silhouette(None, &mut rng), Some(1.0));
+    assert_eq!(collection.silhouette(Some(2), &mut rng), Some(1.0));
+
+    // the last individual is closer to the other species
+    collection.get_mut(1).insert(individual(4, 1));
+    assert!(collection.silhouette(None, &mut rng).unwrap() < 1.0);
+}
+
+#[test]
+fn nearest_species() {
+    let far = IndividualTest::with_set_genes(1, 6, 6);
+    let mut collection = SpeciesCollection::<IndividualTest, f32>::new();
+    collection.push(Species::new(IndividualTest::empty(0, 6), 1));
+    collection.push(Species::new(far, 2));
+
+    let individual = IndividualTest::with_set_genes(2, 6, 4);
+    assert_eq!(collection.nearest(&individual, &HashSet::new()), Some(1));
+    let excluded: HashSet<usize> = [2].iter().cloned().collect();
+    assert_eq!(collection.nearest(&individual, &excluded), Some(0));
+    let all: HashSet<usize> = [1, 2].iter().cloned().collect();
+    assert_eq!(collection.nearest(&individual, &all), None);
+}
+
+#[test]
+fn kernel_fitness_sharing() {
+    let mut collection = collection(&[&[2.0, 2.0], &[2.0]]);
+    collection.get_mut(1).individual_mut(0).genome = vec![true];
+    let adjusted_fitnesses = |collection: &SpeciesCollection<IndividualTest, f32>| collection.iter()
+        .flat_map(|species| species.iter_with_adjusted_fitness().map(|(_, adjusted)| adjusted.unwrap()))
+        .collect::<Vec<f32>>();
+
+    let conf = Conf { young_age_fitness_boost: 1.0, ..Conf::default() };
+    collection.compute_adjust_fitness(&conf, 0);
+    assert_eq!(adjusted_fitnesses(&collection), vec![1.0, 1.0, 2.0]);
+
+    // niche counts: 1 + 1 + 0.5 in the first species, 0.5 + 0.5 + 1 in the second one
+    let conf = Conf { fitness_sharing: FitnessSharing::Kernel { sigma: 2.0, alpha: 1.0 }, ..conf };
+    collection.compute_adjust_fitness(&conf, 0);
+    assert_eq!(adjusted_fitnesses(&collection), vec![0.8, 0.8, 1.0]);
+}
+
+#[test]
+fn best_match_species() {
+    let mut collection = SpeciesCollection::<IndividualTest, f32>::new();
+    for (id, true_genes) in [1, 3].iter().enumerate() {
+        collection.push(Species::new(IndividualTest::with_set_genes(id, 6, *true_genes), id + 1));
+    }
+
+    // compatible with both species, nearer to the second one
+    let individual = IndividualTest::with_set_genes(2, 6, 3);
+    let conf = Conf::default();
+    assert_eq!(collection.find_compatible(&individual, &conf, &HashSet::new()), Some(0));
+    let conf = Conf { speciation_strategy: SpeciationStrategy::BestMatch, ..Conf::default() };
+    assert_eq!(collection.find_compatible(&individual, &conf, &HashSet::new()), Some(1));
+    let excluded: HashSet<usize> = [2].iter().cloned().collect();
+    assert_eq!(collection.find_compatible(&individual, &conf, &excluded), Some(0));
+
+    let individual = IndividualTest::with_set_genes(2, 6, 6);
+    assert_eq!(collection.find_compatible(&individual, &conf, &HashSet::new()), None);
+}
+
+#[test]
+fn split_oversized_species() {
+    let mut species = Species::new(IndividualTest::empty(0, 6), 1);
+    for id in 1..3 {
+        species.insert(IndividualTest::empty(id, 6));
+    }
+    for id in 3..6 {
+        species.insert(IndividualTest::with_set_genes(id, 6, 6));
+    }
+    let mut collection = SpeciesCollection::new_from_iter(std::iter::once(species));
+    collection.push(Species::new(IndividualTest::empty(6, 6), 2));
+
+    let mut next_species_id = 3;
+    let no_split = Conf::default();
+    assert_eq!(collection.split_oversized(&no_split, &HashSet::new(), &mut next_species_id), 0);
+
+    let conf = Conf {
+        species_split_fraction: Some(0.5),
+        ..Conf::default()
+    };
+    let excluded: HashSet<usize> = [1].iter().cloned().collect();
+    assert_eq!(collection.split_oversized(&conf, &excluded, &mut next_species_id), 0);
+
+    assert_eq!(collection.split_oversized(&conf, &HashSet::new(), &mut next_species_id), 1);
+    assert_eq!(next_species_id, 4);
+    assert_eq!(collection.len(), 3);
+    assert_eq!(collection.get(0).len(), 3);
+    assert_eq!(collection.get(2).id, 3);
+    assert!(collection.get(2).iter().all(|individual| individual.genome.iter().all(|b| *b)));
+}
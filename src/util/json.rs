@@ -0,0 +1,114 @@
+use std::fmt;
+
+/// JSON representation of a number: JSON has no NaN and infinities, they are written as `null`
+pub fn number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// JSON value, `Display` writes it on a single line
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// Written with `number`
+    Number(f64),
+    /// Written exactly, also beyond the integers an f64 can represent
+    Integer(u64),
+    String(String),
+    /// The fields in the order they are written
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn object<'a, It: IntoIterator<Item=(&'a str, Value)>>(fields: It) -> Self {
+        Value::Object(fields.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+    }
+
+    /// Strategy without parameters, represented by its identifier
+    pub fn id(id: &str) -> Self {
+        Value::String(id.to_string())
+    }
+
+    /// Strategy with parameters, an object with the identifier in `kind` followed by the parameters
+    pub fn strategy<'a, It: IntoIterator<Item=(&'a str, Value)>>(id: &str, parameters: It) -> Self {
+        Value::object(std::iter::once(("kind", Value::id(id))).chain(parameters))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Integer(value as u64)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Number(value) => write!(f, "{}", number(*value)),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::String(value) => write_string(f, value),
+            Value::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Writes a JSON string, escaping the quotes, the backslashes and the control characters
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
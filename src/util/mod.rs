@@ -1 +1,2 @@
-pub mod iterators;
\ No newline at end of file
+pub mod iterators;
+pub mod json;